@@ -275,6 +275,7 @@ check-stage$(1)-T-$(2)-H-$(3)-exec: \
 	check-stage$(1)-T-$(2)-H-$(3)-debuginfo-lldb-exec \
 	check-stage$(1)-T-$(2)-H-$(3)-incremental-exec \
 	check-stage$(1)-T-$(2)-H-$(3)-ui-exec \
+	check-stage$(1)-T-$(2)-H-$(3)-rustfix-exec \
 	check-stage$(1)-T-$(2)-H-$(3)-doc-exec \
 	check-stage$(1)-T-$(2)-H-$(3)-doc-error-index-exec \
 	check-stage$(1)-T-$(2)-H-$(3)-pretty-exec \
@@ -460,6 +461,8 @@ UI_RS := $(call rwildcard,$(S)src/test/ui/,*.rs) \
          $(call rwildcard,$(S)src/test/ui/,*.stderr)
 RUSTDOCCK_RS := $(call rwildcard,$(S)src/test/rustdoc/,*.rs)
 MIR_OPT_RS := $(call rwildcard,$(S)src/test/mir-opt/,*.rs)
+RUSTFIX_RS := $(call rwildcard,$(S)src/test/run-rustfix/,*.rs) \
+              $(call rwildcard,$(S)src/test/run-rustfix/,*.fixed)
 
 RPASS_TESTS := $(RPASS_RS)
 RPASS_VALGRIND_TESTS := $(RPASS_VALGRIND_RS)
@@ -478,6 +481,7 @@ INCREMENTAL_TESTS := $(INCREMENTAL_RS)
 RMAKE_TESTS := $(RMAKE_RS)
 UI_TESTS := $(UI_RS)
 MIR_OPT_TESTS := $(MIR_OPT_RS)
+RUSTFIX_TESTS := $(RUSTFIX_RS)
 RUSTDOCCK_TESTS := $(RUSTDOCCK_RS)
 
 CTEST_SRC_BASE_rpass = run-pass
@@ -560,6 +564,11 @@ CTEST_BUILD_BASE_mir-opt = mir-opt
 CTEST_MODE_mir-opt = mir-opt
 CTEST_RUNTOOL_mir-opt = $(CTEST_RUNTOOL)
 
+CTEST_SRC_BASE_rustfix = run-rustfix
+CTEST_BUILD_BASE_rustfix = run-rustfix
+CTEST_MODE_rustfix = run-rustfix
+CTEST_RUNTOOL_rustfix = $(CTEST_RUNTOOL)
+
 CTEST_SRC_BASE_rustdocck = rustdoc
 CTEST_BUILD_BASE_rustdocck = rustdoc
 CTEST_MODE_rustdocck = rustdoc
@@ -694,6 +703,7 @@ CTEST_DEPS_rmake_$(1)-T-$(2)-H-$(3) = $$(RMAKE_TESTS) \
 	$$(CSREQ$(1)_T_$(3)_H_$(3)) $$(SREQ$(1)_T_$(2)_H_$(3))
 CTEST_DEPS_ui_$(1)-T-$(2)-H-$(3) = $$(UI_TESTS)
 CTEST_DEPS_mir-opt_$(1)-T-$(2)-H-$(3) = $$(MIR_OPT_TESTS)
+CTEST_DEPS_rustfix_$(1)-T-$(2)-H-$(3) = $$(RUSTFIX_TESTS)
 CTEST_DEPS_rustdocck_$(1)-T-$(2)-H-$(3) = $$(RUSTDOCCK_TESTS) \
 		$$(HBIN$(1)_H_$(3))/rustdoc$$(X_$(3)) \
 		$(S)src/etc/htmldocck.py
@@ -765,7 +775,7 @@ endef
 
 CTEST_NAMES = rpass rpass-valgrind rpass-full rfail-full cfail-full rfail cfail pfail \
 	debuginfo-gdb debuginfo-lldb codegen codegen-units rustdocck incremental \
-	rmake ui mir-opt
+	rmake ui mir-opt rustfix
 
 $(foreach host,$(CFG_HOST), \
  $(eval $(foreach target,$(CFG_TARGET), \
@@ -965,6 +975,7 @@ TEST_GROUPS = \
 	codegen-units \
 	incremental \
 	ui \
+	rustfix \
 	doc \
 	$(foreach docname,$(DOC_NAMES),doc-$(docname)) \
 	pretty \
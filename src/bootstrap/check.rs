@@ -18,10 +18,14 @@ use std::fs::{self, File};
 use std::io::prelude::*;
 use std::path::{PathBuf, Path};
 use std::process::Command;
+use std::time::Duration;
 
 use build_helper::output;
+use rustc_serialize::json;
+use rustc_serialize::{Encodable, Encoder};
 
 use {Build, Compiler, Mode};
+use step::Step;
 use util::{self, dylib_path, dylib_path_var};
 
 const ADB_TEST_DIR: &'static str = "/data/tmp";
@@ -413,3 +417,114 @@ pub fn android_copy_libs(build: &Build,
         }
     }
 }
+
+/// The outcome of a test step.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Outcome {
+    Passed,
+    Failed,
+    /// The step wasn't executed because a test step it depends on failed.
+    Skipped,
+}
+
+impl Outcome {
+    fn as_str(&self) -> &'static str {
+        match *self {
+            Outcome::Passed => "passed",
+            Outcome::Failed => "failed",
+            Outcome::Skipped => "skipped",
+        }
+    }
+}
+
+impl Encodable for Outcome {
+    fn encode<S: Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
+        s.emit_str(self.as_str())
+    }
+}
+
+#[derive(RustcEncodable)]
+struct StepResult {
+    step: String,
+    target: String,
+    outcome: Outcome,
+    /// Wall-clock time the step took, in seconds.
+    duration: f64,
+    error: Option<String>,
+}
+
+#[derive(RustcEncodable)]
+struct Report<'a> {
+    passed: usize,
+    failed: usize,
+    skipped: usize,
+    steps: &'a [StepResult],
+}
+
+/// Results of all the test steps executed in this build, in the order in
+/// which they were executed.
+pub struct Results {
+    steps: Vec<StepResult>,
+}
+
+impl Results {
+    pub fn new() -> Results {
+        Results { steps: Vec::new() }
+    }
+
+    pub fn record(&mut self,
+                  step: &Step,
+                  outcome: Outcome,
+                  duration: Duration,
+                  error: Option<String>) {
+        self.steps.push(StepResult {
+            step: format!("{:?}", step.src),
+            target: step.target.to_string(),
+            outcome: outcome,
+            duration: duration.as_secs() as f64 +
+                      duration.subsec_nanos() as f64 / 1_000_000_000.0,
+            error: error,
+        });
+    }
+
+    pub fn any_failed(&self) -> bool {
+        self.count(Outcome::Failed) > 0
+    }
+
+    fn count(&self, outcome: Outcome) -> usize {
+        self.steps.iter().filter(|s| s.outcome == outcome).count()
+    }
+
+    /// Prints a table of all test steps along with their outcome and how long
+    /// they took.
+    pub fn print_summary(&self) {
+        if self.steps.is_empty() {
+            return
+        }
+        println!("\ntest steps:");
+        for step in self.steps.iter() {
+            let outcome = match step.outcome {
+                Outcome::Failed => "FAILED",
+                outcome => outcome.as_str(),
+            };
+            println!("    {:<8} {:>9.2}s  {} ({})",
+                     outcome, step.duration, step.step, step.target);
+        }
+        println!("\n{} passed; {} failed; {} skipped\n",
+                 self.count(Outcome::Passed),
+                 self.count(Outcome::Failed),
+                 self.count(Outcome::Skipped));
+    }
+
+    /// Writes the results as JSON to `path`, for consumption by CI.
+    pub fn write_report(&self, path: &Path) {
+        let report = Report {
+            passed: self.count(Outcome::Passed),
+            failed: self.count(Outcome::Failed),
+            skipped: self.count(Outcome::Skipped),
+            steps: &self.steps,
+        };
+        let mut f = t!(File::create(path));
+        t!(write!(f, "{}", json::as_pretty_json(&report)));
+    }
+}
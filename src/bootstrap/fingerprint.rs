@@ -0,0 +1,331 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Fingerprinting of the inputs of build steps.
+//!
+//! Before a step is executed all of its inputs are hashed together: the
+//! sources it compiles, the build configuration from `config.mk` and
+//! `--config`, the stage0 compiler, and the fingerprints of the steps it
+//! depends on. Once the step finishes this
+//! fingerprint is written next to its output, and the next build skips the
+//! step if its fingerprint hasn't changed since.
+//!
+//! Only the steps producing the compiler, its libraries and the build tools
+//! are fingerprinted. Everything else, including all tests, always runs.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+
+use md5;
+
+use step::{Source, Step};
+use util::{mtime, staticlib};
+use {Build, Compiler, Mode};
+
+/// Whether a step needs to be executed, and why.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Status {
+    /// The step isn't fingerprinted, so it's always executed.
+    AlwaysRuns,
+    /// The step has never been executed to completion before.
+    NeverRun,
+    /// Some input of the step changed since it was last executed.
+    Changed,
+    /// None of the inputs of the step changed since it was last executed.
+    UpToDate,
+}
+
+impl Status {
+    pub fn needs_run(&self) -> bool {
+        *self != Status::UpToDate
+    }
+
+    pub fn reason(&self) -> &'static str {
+        match *self {
+            Status::AlwaysRuns => "always runs",
+            Status::NeverRun => "not built yet",
+            Status::Changed => "inputs changed",
+            Status::UpToDate => "up to date",
+        }
+    }
+}
+
+/// The fingerprints of all steps checked so far in this build.
+pub struct Fingerprints<'a> {
+    computed: HashMap<Step<'a>, String>,
+}
+
+impl<'a> Fingerprints<'a> {
+    pub fn new() -> Fingerprints<'a> {
+        Fingerprints { computed: HashMap::new() }
+    }
+
+    /// Computes the fingerprint of `step` and compares it against the one
+    /// recorded when it was last executed.
+    ///
+    /// Steps must be checked in the order returned by `step::all`, so the
+    /// fingerprints of their dependencies are known by then.
+    pub fn check(&mut self, build: &'a Build, step: &Step<'a>) -> Status {
+        let stamp = match stamp(build, step) {
+            Some(stamp) => stamp,
+            None => return Status::AlwaysRuns,
+        };
+        let fingerprint = self.compute(build, step);
+        let mut recorded = String::new();
+        let status = match File::open(&stamp).and_then(|mut f| f.read_to_string(&mut recorded)) {
+            Err(..) => Status::NeverRun,
+            Ok(..) if recorded == fingerprint => Status::UpToDate,
+            Ok(..) => Status::Changed,
+        };
+        self.computed.insert(step.clone(), fingerprint);
+        status
+    }
+
+    /// Records that `step` was executed with the inputs it was last checked
+    /// with.
+    pub fn record(&self, build: &Build, step: &Step<'a>) {
+        if let Some(stamp) = stamp(build, step) {
+            t!(fs::create_dir_all(stamp.parent().unwrap()));
+            let mut f = t!(File::create(&stamp));
+            t!(f.write_all(self.computed[step].as_bytes()));
+        }
+    }
+
+    fn compute(&self, build: &'a Build, step: &Step<'a>) -> String {
+        let mut inputs = format!("{:?}\n", step);
+
+        // `./configure` writes its options to `config.mk` in the build
+        // directory, which is read from the current directory.
+        push_contents(&mut inputs, Path::new("config.mk"));
+        if let Some(ref config) = build.flags.config {
+            push_contents(&mut inputs, config);
+        }
+
+        for tool in &[&build.rustc, &build.cargo] {
+            push_file(&mut inputs, tool);
+        }
+
+        for dep in step.deps(build) {
+            match self.computed.get(&dep) {
+                Some(fingerprint) => inputs.push_str(fingerprint),
+                None => inputs.push_str(&unfingerprinted_state(build, &dep)),
+            }
+            inputs.push('\n');
+        }
+
+        for dir in sources(build, step) {
+            push_dir(&mut inputs, &dir);
+        }
+
+        let digest = md5::compute(inputs.as_bytes());
+        digest.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
+
+/// Returns where the fingerprint of `step` is recorded, or `None` if `step`
+/// isn't fingerprinted.
+///
+/// Fingerprints live in the directories the steps write their output to, so
+/// removing that output also makes the steps run again.
+fn stamp(build: &Build, step: &Step) -> Option<PathBuf> {
+    let libdir = |compiler: &Compiler, name: &str| {
+        build.sysroot_libdir(compiler, step.target)
+             .join(format!(".rustbuild-fingerprint-{}", name))
+    };
+    let tool = |stage: u32, name: &str| {
+        build.cargo_out(&Compiler::new(stage, step.target), Mode::Tool, step.target)
+             .join(format!(".rustbuild-fingerprint-{}", name))
+    };
+    let stamp = match step.src {
+        Source::Rustc { stage: 0 } => return None,
+        Source::Rustc { stage } => {
+            build.sysroot(&Compiler::new(stage, step.target)).join(".rustbuild-fingerprint")
+        }
+        Source::Libstd { compiler } => libdir(&compiler, "std"),
+        Source::Libtest { compiler } => libdir(&compiler, "test"),
+        Source::Librustc { compiler } => libdir(&compiler, "rustc"),
+        Source::LibstdLink { compiler, host } => {
+            libdir(&Compiler::new(compiler.stage, host), "std")
+        }
+        Source::LibtestLink { compiler, host } => {
+            libdir(&Compiler::new(compiler.stage, host), "test")
+        }
+        Source::LibrustcLink { compiler, host } => {
+            libdir(&Compiler::new(compiler.stage, host), "rustc")
+        }
+        Source::ToolLinkchecker { stage } => tool(stage, "linkchecker"),
+        Source::ToolRustbook { stage } => tool(stage, "rustbook"),
+        Source::ToolErrorIndex { stage } => tool(stage, "error_index_generator"),
+        Source::ToolCargoTest { stage } => tool(stage, "cargotest"),
+        Source::ToolTidy { stage } => tool(stage, "tidy"),
+        Source::ToolCompiletest { stage } => tool(stage, "compiletest"),
+        _ => return None,
+    };
+    Some(stamp)
+}
+
+/// Returns the source directories compiled by `step`.
+fn sources(build: &Build, step: &Step) -> Vec<PathBuf> {
+    let tool = |name: &str| {
+        vec![build.src.join("src/tools").join(name), build.src.join("src/build_helper")]
+    };
+    match step.src {
+        // The crates of the standard library and the compiler are spread
+        // over `src/lib*`, pull in a few native libraries like the ones in
+        // `src/rt`, and are all sharing the shim crates in `src/rustc`.
+        Source::Libstd { .. } |
+        Source::Libtest { .. } |
+        Source::Librustc { .. } => {
+            let mut dirs = t!(fs::read_dir(build.src.join("src"))).map(|e| t!(e).path())
+                .filter(|path| {
+                    let name = path.file_name().unwrap().to_str().unwrap();
+                    name.starts_with("lib") ||
+                        ["rustc", "rt", "rtstartup", "rustllvm", "build_helper", "jemalloc",
+                         "compiler-rt"]
+                            .contains(&name)
+                })
+                .collect::<Vec<_>>();
+            dirs.sort();
+            dirs
+        }
+        Source::ToolLinkchecker { .. } => tool("linkchecker"),
+        Source::ToolRustbook { .. } => tool("rustbook"),
+        Source::ToolErrorIndex { .. } => tool("error_index_generator"),
+        Source::ToolCargoTest { .. } => tool("cargotest"),
+        Source::ToolTidy { .. } => tool("tidy"),
+        Source::ToolCompiletest { .. } => tool("compiletest"),
+        _ => Vec::new(),
+    }
+}
+
+/// Describes the state of a dependency which isn't fingerprinted itself.
+fn unfingerprinted_state(build: &Build, dep: &Step) -> String {
+    match dep.src {
+        // LLVM has its own logic for when to rebuild, but everything linking
+        // to it needs to be rebuilt when it was.
+        Source::Llvm { .. } => {
+            let done_stamp = build.llvm_out(dep.target).join("llvm-finished-building");
+            let mut state = String::new();
+            push_file(&mut state, &done_stamp);
+            state
+        }
+        // The same goes for compiler-rt, which libstd copies into the
+        // sysroot.
+        Source::CompilerRt { .. } => {
+            let output = build.compiler_rt_out(dep.target)
+                              .join(staticlib("compiler-rt", dep.target));
+            let mut state = String::new();
+            push_file(&mut state, &output);
+            state
+        }
+        _ => format!("{:?}", dep),
+    }
+}
+
+/// Adds the contents of the file at `path`, if it exists.
+fn push_contents(inputs: &mut String, path: &Path) {
+    let mut contents = String::new();
+    if let Ok(..) = File::open(path).and_then(|mut f| f.read_to_string(&mut contents)) {
+        inputs.push_str(&format!("{}\n{}\n", path.display(), contents));
+    }
+}
+
+fn push_file(inputs: &mut String, path: &Path) {
+    let len = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    let mtime = mtime(path);
+    inputs.push_str(&format!("{} {} {}.{:09}\n",
+                             path.display(),
+                             len,
+                             mtime.seconds_relative_to_1970(),
+                             mtime.nanoseconds()));
+}
+
+fn push_dir(inputs: &mut String, dir: &Path) {
+    let mut entries = match fs::read_dir(dir) {
+        Ok(entries) => entries.map(|e| t!(e).path()).collect::<Vec<_>>(),
+        // Submodules which aren't checked out, for example
+        Err(..) => return,
+    };
+    entries.sort();
+    for path in entries {
+        let name = path.file_name().unwrap().to_str().unwrap_or("").to_string();
+        if name.starts_with(".") || name == "target" {
+            continue
+        }
+        if path.is_dir() {
+            push_dir(inputs, &path);
+        } else {
+            push_file(inputs, &path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::prelude::*;
+    use std::path::PathBuf;
+
+    use super::{push_contents, push_dir};
+
+    fn tmpdir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("rustbuild-fingerprint-{}", name));
+        let _ = fs::remove_dir_all(&dir);
+        t!(fs::create_dir_all(&dir));
+        dir
+    }
+
+    fn write(path: &PathBuf, contents: &str) {
+        t!(t!(File::create(path)).write_all(contents.as_bytes()));
+    }
+
+    #[test]
+    fn config_contents_are_hashed() {
+        let dir = tmpdir("config");
+        let config = dir.join("config.mk");
+
+        let mut missing = String::new();
+        push_contents(&mut missing, &config);
+        assert_eq!(missing, "");
+
+        write(&config, "CFG_ENABLE_DEBUG := 1\n");
+        let mut before = String::new();
+        push_contents(&mut before, &config);
+        write(&config, "CFG_ENABLE_DEBUG := 0\n");
+        let mut after = String::new();
+        push_contents(&mut after, &config);
+        assert!(before.contains("CFG_ENABLE_DEBUG := 1"));
+        assert!(before != after);
+    }
+
+    #[test]
+    fn dirs_skip_hidden_files_and_targets() {
+        let dir = tmpdir("dir");
+        t!(fs::create_dir_all(dir.join("target")));
+        t!(fs::create_dir_all(dir.join("sub")));
+        write(&dir.join("sub/lib.rs"), "fn foo() {}");
+        write(&dir.join("target/out"), "");
+        write(&dir.join(".hidden"), "");
+
+        let mut before = String::new();
+        push_dir(&mut before, &dir);
+        assert!(before.contains("lib.rs"));
+        assert!(!before.contains("target"));
+        assert!(!before.contains(".hidden"));
+
+        write(&dir.join("sub/lib.rs"), "fn foo() { bar() }");
+        let mut after = String::new();
+        push_dir(&mut after, &dir);
+        assert!(before != after);
+    }
+}
@@ -33,6 +33,9 @@ pub struct Flags {
     pub jobs: Option<u32>,
     pub args: Vec<String>,
     pub clean: bool,
+    pub dry_run: bool,
+    pub keep_going: bool,
+    pub test_report: Option<PathBuf>,
 }
 
 pub struct Filter {
@@ -52,6 +55,12 @@ impl Flags {
         opts.optopt("", "src", "path to repo root", "DIR");
         opts.optopt("j", "jobs", "number of jobs to run in parallel", "JOBS");
         opts.optflag("", "clean", "clean output directory");
+        opts.optflag("", "dry-run", "print the steps to execute and why, without \
+                                     executing them");
+        opts.optflag("", "keep-going", "keep running the remaining test steps \
+                                        after one of them failed");
+        opts.optopt("", "test-report", "write the results of all test steps as \
+                                        JSON to FILE", "FILE");
         opts.optflag("h", "help", "print this help message");
 
         let usage = |n| -> ! {
@@ -79,6 +88,9 @@ impl Flags {
         Flags {
             verbose: m.opt_present("v"),
             clean: m.opt_present("clean"),
+            dry_run: m.opt_present("dry-run"),
+            keep_going: m.opt_present("keep-going"),
+            test_report: m.opt_str("test-report").map(PathBuf::from),
             stage: m.opt_str("stage").map(|j| j.parse().unwrap()),
             build: m.opt_str("build").unwrap(),
             host: Filter { values: m.opt_strs("host") },
@@ -28,14 +28,15 @@ extern crate rustc_serialize;
 extern crate toml;
 extern crate regex;
 
-use std::cell::RefCell;
-use std::collections::HashMap;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::fs::{self, File};
 use std::path::{Component, PathBuf, Path};
-use std::process::Command;
+use std::process::{self, Command};
+use std::time::{Duration, Instant};
 
-use build_helper::{run_silent, output};
+use build_helper::{run_silent, try_run_silent, output};
 
 use util::{exe, mtime, libdir, add_lib_path};
 
@@ -62,6 +63,7 @@ mod compile;
 mod config;
 mod dist;
 mod doc;
+mod fingerprint;
 mod flags;
 mod native;
 mod sanity;
@@ -132,6 +134,11 @@ pub struct Build {
     cc: HashMap<String, (gcc::Tool, Option<PathBuf>)>,
     cxx: HashMap<String, gcc::Tool>,
     compiler_rt_built: RefCell<HashMap<String, PathBuf>>,
+
+    // Whether commands failing in the current step are only recorded in
+    // `delayed_failure` instead of aborting the build, see `--keep-going`
+    delay_failures: Cell<bool>,
+    delayed_failure: RefCell<Option<String>>,
 }
 
 /// The various "modes" of invoking Cargo.
@@ -202,6 +209,8 @@ impl Build {
             gdb_version: None,
             lldb_version: None,
             lldb_python_dir: None,
+            delay_failures: Cell::new(false),
+            delayed_failure: RefCell::new(None),
         }
     }
 
@@ -233,8 +242,10 @@ impl Build {
             self.verbose(&format!("auto-detected local-rebuild {}", self.release));
             self.local_rebuild = true;
         }
-        self.verbose("updating submodules");
-        self.update_submodules();
+        if !self.flags.dry_run {
+            self.verbose("updating submodules");
+            self.update_submodules();
+        }
 
         // The main loop of the build system.
         //
@@ -246,7 +257,44 @@ impl Build {
         // Almost all of these are simple one-liners that shell out to the
         // corresponding functionality in the extra modules, where more
         // documentation can be found.
+        //
+        // Steps whose inputs haven't changed since they were last executed
+        // are skipped, see the `fingerprint` module for more information.
+        //
+        // With `--keep-going` a failing test step doesn't abort the build,
+        // instead it's recorded in `results` and the steps depending on it
+        // are skipped. With `--test-report` the failure is recorded as well,
+        // but then ends the build unless `--keep-going` is also given.
+        let mut fingerprints = fingerprint::Fingerprints::new();
+        let mut results = check::Results::new();
+        let mut failed = HashSet::new();
         for target in step::all(self) {
+            let status = fingerprints.check(self, &target);
+            if self.flags.dry_run {
+                println!("{:<10} {:?} ({}): {}",
+                         if status.needs_run() { "run" } else { "skip" },
+                         target.src,
+                         target.target,
+                         status.reason());
+                continue
+            }
+            if !status.needs_run() {
+                self.verbose(&format!("skipping {:?} ({}): {}",
+                                      target.src, target.target, status.reason()));
+                continue
+            }
+            if target.deps(self).iter().any(|dep| failed.contains(dep)) {
+                if target.is_test() {
+                    results.record(&target, check::Outcome::Skipped,
+                                   Duration::new(0, 0), None);
+                }
+                failed.insert(target);
+                continue
+            }
+
+            let start = Instant::now();
+            let record_failures = self.flags.keep_going || self.flags.test_report.is_some();
+            self.delay_failures.set(record_failures && target.is_test());
             let doc_out = self.out.join(&target.target).join("doc");
             match target.src {
                 Llvm { _dummy } => {
@@ -407,6 +455,10 @@ impl Build {
                     check::compiletest(self, &compiler, target.target,
                                        "ui", "ui");
                 }
+                CheckRustfix { compiler } => {
+                    check::compiletest(self, &compiler, target.target,
+                                       "run-rustfix", "run-rustfix");
+                }
                 CheckDebuginfo { compiler } => {
                     if target.target.contains("msvc") {
                         // nothing to do
@@ -469,6 +521,35 @@ impl Build {
                 CheckTarget { .. } |
                 Check { .. } => {}
             }
+
+            self.delay_failures.set(false);
+            let failure = self.delayed_failure.borrow_mut().take();
+            if target.is_test() {
+                let outcome = if failure.is_some() {
+                    check::Outcome::Failed
+                } else {
+                    check::Outcome::Passed
+                };
+                results.record(&target, outcome, start.elapsed(), failure.clone());
+            }
+            if failure.is_some() {
+                if !self.flags.keep_going {
+                    break
+                }
+                failed.insert(target);
+                continue
+            }
+            fingerprints.record(self, &target);
+        }
+
+        if self.flags.keep_going {
+            results.print_summary();
+        }
+        if let Some(ref report) = self.flags.test_report {
+            results.write_report(report);
+        }
+        if results.any_failed() {
+            process::exit(1);
         }
     }
 
@@ -896,9 +977,22 @@ impl Build {
     }
 
     /// Runs a command, printing out nice contextual information if it fails.
+    ///
+    /// With `--keep-going` or `--test-report`, a failure in a test step is
+    /// instead recorded and the remaining commands of that step are skipped.
     fn run(&self, cmd: &mut Command) {
         self.verbose(&format!("running: {:?}", cmd));
-        run_silent(cmd)
+        if !self.delay_failures.get() {
+            return run_silent(cmd)
+        }
+        if self.delayed_failure.borrow().is_some() {
+            self.verbose("skipping: an earlier command of this step failed");
+            return
+        }
+        if let Err(e) = try_run_silent(cmd) {
+            println!("\n\n{}\n\n", e);
+            *self.delayed_failure.borrow_mut() = Some(e);
+        }
     }
 
     /// Prints a message if this build is configured in verbose mode.
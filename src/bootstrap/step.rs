@@ -124,6 +124,7 @@ macro_rules! targets {
             (check_incremental, CheckIncremental { compiler: Compiler<'a> }),
             (check_ui, CheckUi { compiler: Compiler<'a> }),
             (check_mir_opt, CheckMirOpt { compiler: Compiler<'a> }),
+            (check_rustfix, CheckRustfix { compiler: Compiler<'a> }),
             (check_debuginfo, CheckDebuginfo { compiler: Compiler<'a> }),
             (check_rustdoc, CheckRustdoc { compiler: Compiler<'a> }),
             (check_docs, CheckDocs { compiler: Compiler<'a> }),
@@ -310,6 +311,45 @@ impl<'a> Step<'a> {
         Step { target: target, src: self.src.clone() }
     }
 
+    /// Returns whether this step runs a test suite, as opposed to building
+    /// something or just grouping other steps together.
+    pub fn is_test(&self) -> bool {
+        match self.src {
+            Source::CheckLinkcheck { .. } |
+            Source::CheckCargoTest { .. } |
+            Source::CheckTidy { .. } |
+            Source::CheckRPass { .. } |
+            Source::CheckRPassFull { .. } |
+            Source::CheckRPassValgrind { .. } |
+            Source::CheckRFail { .. } |
+            Source::CheckRFailFull { .. } |
+            Source::CheckCFail { .. } |
+            Source::CheckCFailFull { .. } |
+            Source::CheckPFail { .. } |
+            Source::CheckPretty { .. } |
+            Source::CheckPrettyRPass { .. } |
+            Source::CheckPrettyRPassFull { .. } |
+            Source::CheckPrettyRFail { .. } |
+            Source::CheckPrettyRFailFull { .. } |
+            Source::CheckPrettyRPassValgrind { .. } |
+            Source::CheckCodegen { .. } |
+            Source::CheckCodegenUnits { .. } |
+            Source::CheckIncremental { .. } |
+            Source::CheckUi { .. } |
+            Source::CheckMirOpt { .. } |
+            Source::CheckRustfix { .. } |
+            Source::CheckDebuginfo { .. } |
+            Source::CheckRustdoc { .. } |
+            Source::CheckDocs { .. } |
+            Source::CheckErrorIndex { .. } |
+            Source::CheckRMake { .. } |
+            Source::CheckCrateStd { .. } |
+            Source::CheckCrateTest { .. } |
+            Source::CheckCrateRustc { .. } => true,
+            _ => false,
+        }
+    }
+
     // Define ergonomic constructors for each step defined above so they can be
     // easily constructed.
     targets!(constructors);
@@ -435,6 +475,7 @@ impl<'a> Step<'a> {
                         self.check_cfail(compiler),
                         self.check_pfail(compiler),
                         self.check_ui(compiler),
+                        self.check_rustfix(compiler),
 
                         // codegen-related
                         self.check_incremental(compiler),
@@ -478,6 +519,7 @@ impl<'a> Step<'a> {
                 vec![self.tool_tidy(stage)]
             }
             Source::CheckMirOpt { compiler} |
+            Source::CheckRustfix { compiler } |
             Source::CheckPrettyRPass { compiler } |
             Source::CheckPrettyRFail { compiler } |
             Source::CheckRFail { compiler } |
@@ -19,14 +19,23 @@ pub fn run(cmd: &mut Command) {
 }
 
 pub fn run_silent(cmd: &mut Command) {
+    if let Err(e) = try_run_silent(cmd) {
+        fail(&e);
+    }
+}
+
+/// Like `run_silent`, but returns a description of the failure instead of
+/// exiting the process.
+pub fn try_run_silent(cmd: &mut Command) -> Result<(), String> {
     let status = match cmd.status() {
         Ok(status) => status,
-        Err(e) => fail(&format!("failed to execute command: {}", e)),
+        Err(e) => return Err(format!("failed to execute command: {}", e)),
     };
     if !status.success() {
-        fail(&format!("command did not execute successfully: {:?}\n\
-                       expected success, got: {}", cmd, status));
+        return Err(format!("command did not execute successfully: {:?}\n\
+                            expected success, got: {}", cmd, status))
     }
+    Ok(())
 }
 
 pub fn gnu_target(target: &str) -> String {
@@ -0,0 +1,125 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Lint levels read from the file given with `--lint-config`, which lets
+//! several crates share one lint policy:
+//!
+//! ```text
+//! # Levels for every crate
+//! unused_imports = deny
+//! missing_docs = warn
+//!
+//! # Overrides for the crates whose root source file is in `src/generated`
+//! [src/generated]
+//! missing_docs = allow
+//! ```
+//!
+//! The settings before the first section apply to all crates. The settings
+//! of a section only apply to crates whose root source file is in the path
+//! of the section, which is relative to the directory of the configuration
+//! file. Later settings override earlier ones, and the levels given with
+//! `-A`, `-W`, `-D` and `-F` override the configuration.
+
+use lint::Level;
+
+use std::env;
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+
+/// The level of one lint or lint group in the configuration.
+#[derive(Clone, Hash)]
+pub struct Setting {
+    /// The path the setting is restricted to, if any.
+    pub path: Option<PathBuf>,
+    pub lint_name: String,
+    pub level: Level,
+    /// The line the setting is on, for diagnostics.
+    pub line: usize,
+}
+
+#[derive(Clone, Hash)]
+pub struct LintConfig {
+    pub file: PathBuf,
+    settings: Vec<Setting>,
+}
+
+impl LintConfig {
+    pub fn load(file: &Path) -> Result<LintConfig, String> {
+        let mut contents = String::new();
+        File::open(file).and_then(|mut f| f.read_to_string(&mut contents)).map_err(|e| {
+            format!("failed to read lint configuration `{}`: {}", file.display(), e)
+        })?;
+        LintConfig::parse(file, &contents)
+    }
+
+    pub fn parse(file: &Path, contents: &str) -> Result<LintConfig, String> {
+        let dir = absolute(file.parent().unwrap_or(Path::new("")));
+        let mut path = None;
+        let mut settings = Vec::new();
+        for (i, line) in contents.lines().enumerate() {
+            let line = match line.find('#') {
+                Some(pos) => &line[..pos],
+                None => line,
+            }.trim();
+            if line.is_empty() {
+                continue
+            }
+            let error = |msg: &str| format!("{}:{}: {}", file.display(), i + 1, msg);
+
+            if line.starts_with('[') && line.ends_with(']') {
+                path = Some(dir.join(line[1..line.len() - 1].trim()));
+                continue
+            }
+
+            let mut parts = line.splitn(2, '=').map(|s| s.trim());
+            let lint_name = parts.next().unwrap();
+            let level = match parts.next() {
+                Some(level) => level,
+                None => return Err(error("expected a section or a `lint = level` setting")),
+            };
+            let level = match Level::from_str(level) {
+                Some(level) => level,
+                None => return Err(error(&format!("unknown lint level: `{}`", level))),
+            };
+            settings.push(Setting {
+                path: path.clone(),
+                lint_name: lint_name.replace("-", "_"),
+                level: level,
+                line: i + 1,
+            });
+        }
+        Ok(LintConfig {
+            file: file.to_path_buf(),
+            settings: settings,
+        })
+    }
+
+    /// Returns the settings applying to the crate with root source file
+    /// `crate_root`, in the order they are to be applied.
+    pub fn settings(&self, crate_root: Option<&Path>) -> Vec<&Setting> {
+        let crate_root = crate_root.map(absolute);
+        self.settings.iter().filter(|setting| {
+            match (&setting.path, &crate_root) {
+                (&None, _) => true,
+                (&Some(ref path), &Some(ref root)) => root.starts_with(absolute(path)),
+                (&Some(_), &None) => false,
+            }
+        }).collect()
+    }
+}
+
+/// Makes `path` absolute, resolving symbolic links if it exists.
+fn absolute(path: &Path) -> PathBuf {
+    match fs::canonicalize(path) {
+        Ok(path) => path,
+        Err(..) => env::current_dir().map(|dir| dir.join(path)).unwrap_or(path.to_path_buf()),
+    }
+}
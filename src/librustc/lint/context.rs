@@ -31,7 +31,7 @@ use ty::TyCtxt;
 use session::{config, early_error, Session};
 use lint::{Level, LevelSource, Lint, LintId, LintPass, LintSource};
 use lint::{EarlyLintPassObject, LateLintPassObject};
-use lint::{Default, CommandLine, Node, Allow, Warn, Deny, Forbid};
+use lint::{Default, CommandLine, ConfigFile, Node, Allow, Warn, Deny, Forbid};
 use lint::builtin;
 use util::nodemap::FnvHashMap;
 
@@ -254,32 +254,21 @@ impl LintStore {
     }
 
     pub fn process_command_line(&mut self, sess: &Session) {
+        // The configuration file comes first so the flags can override it.
+        if let Some(ref config) = sess.opts.lint_config {
+            let crate_root = sess.local_crate_source_file.as_ref().map(|p| p.as_path());
+            for setting in config.settings(crate_root) {
+                let requested = format!("requested in the lint configuration at `{}:{}`",
+                                        config.file.display(), setting.line);
+                report_lint_name(sess, self, &setting.lint_name, &requested);
+                self.set_level_by_name(sess, &setting.lint_name, (setting.level, ConfigFile));
+            }
+        }
+
         for &(ref lint_name, level) in &sess.opts.lint_opts {
             check_lint_name_cmdline(sess, self,
                                     &lint_name[..], level);
-
-            match self.find_lint(&lint_name[..], sess, None) {
-                Ok(lint_id) => self.set_level(lint_id, (level, CommandLine)),
-                Err(FindLintError::Removed) => { }
-                Err(_) => {
-                    match self.lint_groups.iter().map(|(&x, pair)| (x, pair.0.clone()))
-                                                 .collect::<FnvHashMap<&'static str,
-                                                                       Vec<LintId>>>()
-                                                 .get(&lint_name[..]) {
-                        Some(v) => {
-                            v.iter()
-                             .map(|lint_id: &LintId|
-                                     self.set_level(*lint_id, (level, CommandLine)))
-                             .collect::<Vec<()>>();
-                        }
-                        None => {
-                            // The lint or lint group doesn't exist.
-                            // This is an error, but it was handled
-                            // by check_lint_name_cmdline.
-                        }
-                    }
-                }
-            }
+            self.set_level_by_name(sess, lint_name, (level, CommandLine));
         }
 
         self.lint_cap = sess.opts.lint_cap;
@@ -289,6 +278,31 @@ impl LintStore {
             }
         }
     }
+
+    /// Sets the level of the lint or lint group called `lint_name`.
+    fn set_level_by_name(&mut self, sess: &Session, lint_name: &str, lvlsrc: LevelSource) {
+        match self.find_lint(lint_name, sess, None) {
+            Ok(lint_id) => self.set_level(lint_id, lvlsrc),
+            Err(FindLintError::Removed) => { }
+            Err(_) => {
+                match self.lint_groups.iter().map(|(&x, pair)| (x, pair.0.clone()))
+                                             .collect::<FnvHashMap<&'static str,
+                                                                   Vec<LintId>>>()
+                                             .get(lint_name) {
+                    Some(v) => {
+                        v.iter()
+                         .map(|lint_id: &LintId| self.set_level(*lint_id, lvlsrc))
+                         .collect::<Vec<()>>();
+                    }
+                    None => {
+                        // The lint or lint group doesn't exist.
+                        // This is an error, but it was handled when
+                        // the level was requested.
+                    }
+                }
+            }
+        }
+    }
 }
 
 /// Context for lint checking after type checking.
@@ -423,6 +437,9 @@ pub fn raw_struct_lint<'a>(sess: &'a Session,
                         Allow => bug!()
                     }, name.replace("_", "-"))
         },
+        ConfigFile => {
+            format!("{} [`{} = {}` in the lint configuration]", msg, name, level.as_str())
+        },
         Node(src) => {
             def = Some(src);
             msg.to_string()
@@ -614,6 +631,10 @@ pub trait LintContext: Sized {
                         LintSource::CommandLine => {
                             diag_builder.note("`forbid` lint level was set on command line")
                         }
+                        LintSource::ConfigFile => {
+                            diag_builder.note("`forbid` lint level was set in the lint \
+                                               configuration")
+                        }
                     }.emit()
                 } else if now != level {
                     let src = self.lints().get_level_source(lint_id).1;
@@ -1159,6 +1180,21 @@ fn check_lint_name_attribute(cx: &LateContext, attr: &ast::Attribute) {
 // Checks the validity of lint names derived from the command line
 fn check_lint_name_cmdline(sess: &Session, lint_cx: &LintStore,
                            lint_name: &str, level: Level) {
+    let requested = format!("requested on the command line with `{} {}`",
+                            match level {
+                                Level::Allow => "-A",
+                                Level::Warn => "-W",
+                                Level::Deny => "-D",
+                                Level::Forbid => "-F",
+                            },
+                            lint_name);
+    report_lint_name(sess, lint_cx, lint_name, &requested);
+}
+
+/// Reports lints which don't exist or have been renamed or removed, noting
+/// where their level was `requested`.
+fn report_lint_name(sess: &Session, lint_cx: &LintStore,
+                    lint_name: &str, requested: &str) {
     let db = match check_lint_name(lint_cx, lint_name) {
         CheckLintNameResult::Ok => None,
         CheckLintNameResult::Warning(ref msg) => {
@@ -1170,15 +1206,7 @@ fn check_lint_name_cmdline(sess: &Session, lint_cx: &LintStore,
     };
 
     if let Some(mut db) = db {
-        let msg = format!("requested on the command line with `{} {}`",
-                          match level {
-                              Level::Allow => "-A",
-                              Level::Warn => "-W",
-                              Level::Deny => "-D",
-                              Level::Forbid => "-F",
-                          },
-                          lint_name);
-        db.note(&msg);
+        db.note(requested);
         db.emit();
     }
 }
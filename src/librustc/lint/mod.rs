@@ -42,6 +42,7 @@ use hir;
 pub use lint::context::{LateContext, EarlyContext, LintContext, LintStore,
                         raw_emit_lint, check_crate, check_ast_crate, gather_attrs,
                         raw_struct_lint, FutureIncompatibleInfo};
+pub use lint::config::LintConfig;
 
 /// Specification of a single lint.
 #[derive(Copy, Clone, Debug)]
@@ -309,10 +310,14 @@ pub enum LintSource {
 
     /// Lint level was set by a command-line flag.
     CommandLine,
+
+    /// Lint level was set in the file given with `--lint-config`.
+    ConfigFile,
 }
 
 pub type LevelSource = (Level, LintSource);
 
 pub mod builtin;
 
+mod config;
 mod context;
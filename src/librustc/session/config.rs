@@ -41,7 +41,7 @@ use std::env;
 use std::fmt;
 use std::hash::{Hasher, SipHasher};
 use std::iter::FromIterator;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 pub struct Config {
     pub target: Target,
@@ -80,7 +80,11 @@ pub enum OutputType {
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ErrorOutputType {
     HumanReadable(ColorConfig),
-    Json,
+    /// The color configuration applies to the human-readable rendering
+    /// embedded in the JSON diagnostics.
+    Json(ColorConfig),
+    /// One line per diagnostic, without snippets and never colored.
+    Short,
 }
 
 impl Default for ErrorOutputType {
@@ -255,6 +259,9 @@ top_level_options!(
         debuginfo: DebugInfoLevel [TRACKED],
         lint_opts: Vec<(String, lint::Level)> [TRACKED],
         lint_cap: Option<lint::Level> [TRACKED],
+        // The parsed settings are tracked rather than the path, so that
+        // editing the file is noticed.
+        lint_config: Option<lint::LintConfig> [TRACKED],
         describe_lints: bool [UNTRACKED],
         output_types: OutputTypes [TRACKED],
         // FIXME(mw): We track this for now but it actually doesn't make too
@@ -422,6 +429,7 @@ pub fn basic_options() -> Options {
         debuginfo: NoDebugInfo,
         lint_opts: Vec::new(),
         lint_cap: None,
+        lint_config: None,
         describe_lints: false,
         output_types: OutputTypes(BTreeMap::new()),
         search_paths: SearchPaths::new(),
@@ -810,6 +818,9 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
         "in general, enable more debug printouts"),
     time_passes: bool = (false, parse_bool, [UNTRACKED],
         "measure time of each rustc pass"),
+    self_profile: bool = (false, parse_bool, [UNTRACKED],
+        "record the time of each rustc pass and of the work on each item into a trace \
+         readable by chrome://tracing"),
     count_llvm_insns: bool = (false, parse_bool,
         [UNTRACKED_WITH_WARNING(true,
         "The output generated by `-Z count_llvm_insns` might not be reliable \
@@ -870,6 +881,11 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
           "run all passes except translation; no output"),
     treat_err_as_bug: bool = (false, parse_bool, [TRACKED],
           "treat all errors that occur as bugs"),
+    error_code_limit: Option<usize> = (None, parse_opt_uint, [UNTRACKED],
+          "emit at most this many errors of each error code"),
+    check_explanations: bool = (false, parse_bool, [UNTRACKED],
+          "check that the examples in the explanations of the error codes, or of the one \
+           given with --explain, behave as stated"),
     continue_parse_after_error: bool = (false, parse_bool, [TRACKED],
           "attempt to recover from parse errors (experimental)"),
     incremental: Option<String> = (None, parse_opt_string, [UNTRACKED],
@@ -1188,7 +1204,7 @@ pub fn rustc_optgroups() -> Vec<RustcOptGroup> {
         opt::multi_ubnr("Z", "", "Set internal debugging options", "FLAG"),
         opt::opt_s("", "error-format",
                       "How errors and other messages are produced",
-                      "human|json"),
+                      "human|json|short"),
         opt::opt_s("", "color", "Configure coloring of output:
                                  auto   = colorize, if output goes to a tty (default);
                                  always = always colorize output;
@@ -1208,6 +1224,8 @@ pub fn rustc_optgroups() -> Vec<RustcOptGroup> {
                            `hir` (the HIR), `hir,identified`, or
                            `hir,typed` (HIR with types for each node).",
                           "TYPE"),
+        opt::opt("", "lint-config", "Read lint levels from a configuration file, which \
+                                     the -A, -W, -D and -F flags override", "PATH"),
 
         // new options here should **not** use the `_ubnr` functions, all new
         // unstable options should use the short variants to indicate that they
@@ -1262,14 +1280,15 @@ pub fn build_session_options_and_crate_config(matches: &getopts::Matches)
     let error_format = if matches.opts_present(&["error-format".to_owned()]) {
         match matches.opt_str("error-format").as_ref().map(|s| &s[..]) {
             Some("human")   => ErrorOutputType::HumanReadable(color),
-            Some("json") => ErrorOutputType::Json,
+            Some("json") => ErrorOutputType::Json(color),
+            Some("short") => ErrorOutputType::Short,
 
             None => ErrorOutputType::HumanReadable(color),
 
             Some(arg) => {
                 early_error(ErrorOutputType::HumanReadable(color),
-                            &format!("argument for --error-format must be human or json (instead \
-                                      was `{}`)",
+                            &format!("argument for --error-format must be human, json or short \
+                                      (instead was `{}`)",
                                      arg))
             }
         }
@@ -1300,6 +1319,10 @@ pub fn build_session_options_and_crate_config(matches: &getopts::Matches)
         })
     });
 
+    let lint_config = matches.opt_str("lint-config").map(|path| {
+        lint::LintConfig::load(Path::new(&path)).unwrap_or_else(|e| early_error(error_format, &e))
+    });
+
     let debugging_opts = build_debugging_options(matches, error_format);
 
     let mir_opt_level = debugging_opts.mir_opt_level.unwrap_or(1);
@@ -1499,6 +1522,7 @@ pub fn build_session_options_and_crate_config(matches: &getopts::Matches)
         debuginfo: debuginfo,
         lint_opts: lint_opts,
         lint_cap: lint_cap,
+        lint_config: lint_config,
         describe_lints: describe_lints,
         output_types: OutputTypes(output_types),
         search_paths: search_paths,
@@ -1707,6 +1731,7 @@ mod dep_tracking {
     impl_dep_tracking_hash_via_hash!(Option<String>);
     impl_dep_tracking_hash_via_hash!(Option<lint::Level>);
     impl_dep_tracking_hash_via_hash!(Option<PathBuf>);
+    impl_dep_tracking_hash_via_hash!(Option<lint::LintConfig>);
     impl_dep_tracking_hash_via_hash!(CrateType);
     impl_dep_tracking_hash_via_hash!(PanicStrategy);
     impl_dep_tracking_hash_via_hash!(Passes);
@@ -1769,7 +1794,7 @@ mod tests {
     use session::build_session;
     use std::collections::{BTreeMap, BTreeSet};
     use std::iter::FromIterator;
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
     use std::rc::Rc;
     use super::{OutputType, OutputTypes, Externs, PanicStrategy};
     use syntax::{ast, attr};
@@ -2017,6 +2042,27 @@ mod tests {
         assert_eq!(v3.dep_tracking_hash(), v3.clone().dep_tracking_hash());
     }
 
+    #[test]
+    fn test_lint_config_tracking_hash_different_contents() {
+        let mut v1 = super::basic_options();
+        let mut v2 = super::basic_options();
+        let mut v3 = super::basic_options();
+
+        let config = |contents: &str| {
+            lint::LintConfig::parse(Path::new("lints"), contents).unwrap()
+        };
+        v1.lint_config = Some(config("a = deny"));
+        v2.lint_config = Some(config("a = allow"));
+        v3.lint_config = Some(config("a = deny"));
+
+        assert!(v1.dep_tracking_hash() != v2.dep_tracking_hash());
+        assert_eq!(v1.dep_tracking_hash(), v3.dep_tracking_hash());
+
+        // Check clone
+        assert_eq!(v1.dep_tracking_hash(), v1.clone().dep_tracking_hash());
+        assert_eq!(v2.dep_tracking_hash(), v2.clone().dep_tracking_hash());
+    }
+
     #[test]
     fn test_lints_tracking_hash_different_construction_order() {
         let mut v1 = super::basic_options();
@@ -2048,46 +2094,46 @@ mod tests {
         let mut v5 = super::basic_options();
 
         // Reference
-        v1.search_paths.add_path("native=abc", super::ErrorOutputType::Json);
-        v1.search_paths.add_path("crate=def", super::ErrorOutputType::Json);
-        v1.search_paths.add_path("dependency=ghi", super::ErrorOutputType::Json);
-        v1.search_paths.add_path("framework=jkl", super::ErrorOutputType::Json);
-        v1.search_paths.add_path("all=mno", super::ErrorOutputType::Json);
+        v1.search_paths.add_path("native=abc", super::ErrorOutputType::default());
+        v1.search_paths.add_path("crate=def", super::ErrorOutputType::default());
+        v1.search_paths.add_path("dependency=ghi", super::ErrorOutputType::default());
+        v1.search_paths.add_path("framework=jkl", super::ErrorOutputType::default());
+        v1.search_paths.add_path("all=mno", super::ErrorOutputType::default());
 
         // Native changed
-        v2.search_paths.add_path("native=XXX", super::ErrorOutputType::Json);
-        v2.search_paths.add_path("crate=def", super::ErrorOutputType::Json);
-        v2.search_paths.add_path("dependency=ghi", super::ErrorOutputType::Json);
-        v2.search_paths.add_path("framework=jkl", super::ErrorOutputType::Json);
-        v2.search_paths.add_path("all=mno", super::ErrorOutputType::Json);
+        v2.search_paths.add_path("native=XXX", super::ErrorOutputType::default());
+        v2.search_paths.add_path("crate=def", super::ErrorOutputType::default());
+        v2.search_paths.add_path("dependency=ghi", super::ErrorOutputType::default());
+        v2.search_paths.add_path("framework=jkl", super::ErrorOutputType::default());
+        v2.search_paths.add_path("all=mno", super::ErrorOutputType::default());
 
         // Crate changed
-        v2.search_paths.add_path("native=abc", super::ErrorOutputType::Json);
-        v2.search_paths.add_path("crate=XXX", super::ErrorOutputType::Json);
-        v2.search_paths.add_path("dependency=ghi", super::ErrorOutputType::Json);
-        v2.search_paths.add_path("framework=jkl", super::ErrorOutputType::Json);
-        v2.search_paths.add_path("all=mno", super::ErrorOutputType::Json);
+        v2.search_paths.add_path("native=abc", super::ErrorOutputType::default());
+        v2.search_paths.add_path("crate=XXX", super::ErrorOutputType::default());
+        v2.search_paths.add_path("dependency=ghi", super::ErrorOutputType::default());
+        v2.search_paths.add_path("framework=jkl", super::ErrorOutputType::default());
+        v2.search_paths.add_path("all=mno", super::ErrorOutputType::default());
 
         // Dependency changed
-        v3.search_paths.add_path("native=abc", super::ErrorOutputType::Json);
-        v3.search_paths.add_path("crate=def", super::ErrorOutputType::Json);
-        v3.search_paths.add_path("dependency=XXX", super::ErrorOutputType::Json);
-        v3.search_paths.add_path("framework=jkl", super::ErrorOutputType::Json);
-        v3.search_paths.add_path("all=mno", super::ErrorOutputType::Json);
+        v3.search_paths.add_path("native=abc", super::ErrorOutputType::default());
+        v3.search_paths.add_path("crate=def", super::ErrorOutputType::default());
+        v3.search_paths.add_path("dependency=XXX", super::ErrorOutputType::default());
+        v3.search_paths.add_path("framework=jkl", super::ErrorOutputType::default());
+        v3.search_paths.add_path("all=mno", super::ErrorOutputType::default());
 
         // Framework changed
-        v4.search_paths.add_path("native=abc", super::ErrorOutputType::Json);
-        v4.search_paths.add_path("crate=def", super::ErrorOutputType::Json);
-        v4.search_paths.add_path("dependency=ghi", super::ErrorOutputType::Json);
-        v4.search_paths.add_path("framework=XXX", super::ErrorOutputType::Json);
-        v4.search_paths.add_path("all=mno", super::ErrorOutputType::Json);
+        v4.search_paths.add_path("native=abc", super::ErrorOutputType::default());
+        v4.search_paths.add_path("crate=def", super::ErrorOutputType::default());
+        v4.search_paths.add_path("dependency=ghi", super::ErrorOutputType::default());
+        v4.search_paths.add_path("framework=XXX", super::ErrorOutputType::default());
+        v4.search_paths.add_path("all=mno", super::ErrorOutputType::default());
 
         // All changed
-        v5.search_paths.add_path("native=abc", super::ErrorOutputType::Json);
-        v5.search_paths.add_path("crate=def", super::ErrorOutputType::Json);
-        v5.search_paths.add_path("dependency=ghi", super::ErrorOutputType::Json);
-        v5.search_paths.add_path("framework=jkl", super::ErrorOutputType::Json);
-        v5.search_paths.add_path("all=XXX", super::ErrorOutputType::Json);
+        v5.search_paths.add_path("native=abc", super::ErrorOutputType::default());
+        v5.search_paths.add_path("crate=def", super::ErrorOutputType::default());
+        v5.search_paths.add_path("dependency=ghi", super::ErrorOutputType::default());
+        v5.search_paths.add_path("framework=jkl", super::ErrorOutputType::default());
+        v5.search_paths.add_path("all=XXX", super::ErrorOutputType::default());
 
         assert!(v1.dep_tracking_hash() != v2.dep_tracking_hash());
         assert!(v1.dep_tracking_hash() != v3.dep_tracking_hash());
@@ -2110,29 +2156,29 @@ mod tests {
         let mut v4 = super::basic_options();
 
         // Reference
-        v1.search_paths.add_path("native=abc", super::ErrorOutputType::Json);
-        v1.search_paths.add_path("crate=def", super::ErrorOutputType::Json);
-        v1.search_paths.add_path("dependency=ghi", super::ErrorOutputType::Json);
-        v1.search_paths.add_path("framework=jkl", super::ErrorOutputType::Json);
-        v1.search_paths.add_path("all=mno", super::ErrorOutputType::Json);
-
-        v2.search_paths.add_path("native=abc", super::ErrorOutputType::Json);
-        v2.search_paths.add_path("dependency=ghi", super::ErrorOutputType::Json);
-        v2.search_paths.add_path("crate=def", super::ErrorOutputType::Json);
-        v2.search_paths.add_path("framework=jkl", super::ErrorOutputType::Json);
-        v2.search_paths.add_path("all=mno", super::ErrorOutputType::Json);
-
-        v3.search_paths.add_path("crate=def", super::ErrorOutputType::Json);
-        v3.search_paths.add_path("framework=jkl", super::ErrorOutputType::Json);
-        v3.search_paths.add_path("native=abc", super::ErrorOutputType::Json);
-        v3.search_paths.add_path("dependency=ghi", super::ErrorOutputType::Json);
-        v3.search_paths.add_path("all=mno", super::ErrorOutputType::Json);
-
-        v4.search_paths.add_path("all=mno", super::ErrorOutputType::Json);
-        v4.search_paths.add_path("native=abc", super::ErrorOutputType::Json);
-        v4.search_paths.add_path("crate=def", super::ErrorOutputType::Json);
-        v4.search_paths.add_path("dependency=ghi", super::ErrorOutputType::Json);
-        v4.search_paths.add_path("framework=jkl", super::ErrorOutputType::Json);
+        v1.search_paths.add_path("native=abc", super::ErrorOutputType::default());
+        v1.search_paths.add_path("crate=def", super::ErrorOutputType::default());
+        v1.search_paths.add_path("dependency=ghi", super::ErrorOutputType::default());
+        v1.search_paths.add_path("framework=jkl", super::ErrorOutputType::default());
+        v1.search_paths.add_path("all=mno", super::ErrorOutputType::default());
+
+        v2.search_paths.add_path("native=abc", super::ErrorOutputType::default());
+        v2.search_paths.add_path("dependency=ghi", super::ErrorOutputType::default());
+        v2.search_paths.add_path("crate=def", super::ErrorOutputType::default());
+        v2.search_paths.add_path("framework=jkl", super::ErrorOutputType::default());
+        v2.search_paths.add_path("all=mno", super::ErrorOutputType::default());
+
+        v3.search_paths.add_path("crate=def", super::ErrorOutputType::default());
+        v3.search_paths.add_path("framework=jkl", super::ErrorOutputType::default());
+        v3.search_paths.add_path("native=abc", super::ErrorOutputType::default());
+        v3.search_paths.add_path("dependency=ghi", super::ErrorOutputType::default());
+        v3.search_paths.add_path("all=mno", super::ErrorOutputType::default());
+
+        v4.search_paths.add_path("all=mno", super::ErrorOutputType::default());
+        v4.search_paths.add_path("native=abc", super::ErrorOutputType::default());
+        v4.search_paths.add_path("crate=def", super::ErrorOutputType::default());
+        v4.search_paths.add_path("dependency=ghi", super::ErrorOutputType::default());
+        v4.search_paths.add_path("framework=jkl", super::ErrorOutputType::default());
 
         assert!(v1.dep_tracking_hash() == v2.dep_tracking_hash());
         assert!(v1.dep_tracking_hash() == v3.dep_tracking_hash());
@@ -2331,6 +2377,8 @@ mod tests {
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.time_passes = true;
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.self_profile = true;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.count_llvm_insns = true;
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.time_llvm_passes = true;
@@ -2373,6 +2421,10 @@ mod tests {
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.parse_only = true;
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.error_code_limit = Some(1);
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.check_explanations = true;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.incremental = Some(String::from("abc"));
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.dump_dep_graph = true;
@@ -23,6 +23,7 @@ use mir::transform as mir_pass;
 use syntax::ast::{NodeId, Name};
 use errors::{self, DiagnosticBuilder};
 use errors::emitter::{Emitter, EmitterWriter};
+use errors::short::ShortEmitter;
 use syntax::json::JsonEmitter;
 use syntax::feature_gate;
 use syntax::parse;
@@ -443,8 +444,11 @@ pub fn build_session_with_codemap(sopts: config::Options,
             Box::new(EmitterWriter::stderr(color_config,
                                            Some(codemap.clone())))
         }
-        config::ErrorOutputType::Json => {
-            Box::new(JsonEmitter::stderr(Some(registry), codemap.clone()))
+        config::ErrorOutputType::Json(color_config) => {
+            Box::new(JsonEmitter::stderr(Some(registry), codemap.clone(), color_config))
+        }
+        config::ErrorOutputType::Short => {
+            Box::new(ShortEmitter::stderr(Some(codemap.clone())))
         }
     };
 
@@ -452,6 +456,7 @@ pub fn build_session_with_codemap(sopts: config::Options,
         errors::Handler::with_emitter(can_print_warnings,
                                       treat_err_as_bug,
                                       emitter);
+    diagnostic_handler.set_error_code_limit(sopts.debugging_opts.error_code_limit);
 
     build_session_(sopts,
                    dep_graph,
@@ -607,7 +612,10 @@ pub fn early_error(output: config::ErrorOutputType, msg: &str) -> ! {
             Box::new(EmitterWriter::stderr(color_config,
                                            None))
         }
-        config::ErrorOutputType::Json => Box::new(JsonEmitter::basic()),
+        config::ErrorOutputType::Json(color_config) => {
+            Box::new(JsonEmitter::basic(color_config))
+        }
+        config::ErrorOutputType::Short => Box::new(ShortEmitter::stderr(None)),
     };
     let handler = errors::Handler::with_emitter(true, false, emitter);
     handler.emit(&MultiSpan::new(), msg, errors::Level::Fatal);
@@ -620,7 +628,10 @@ pub fn early_warn(output: config::ErrorOutputType, msg: &str) {
             Box::new(EmitterWriter::stderr(color_config,
                                            None))
         }
-        config::ErrorOutputType::Json => Box::new(JsonEmitter::basic()),
+        config::ErrorOutputType::Json(color_config) => {
+            Box::new(JsonEmitter::basic(color_config))
+        }
+        config::ErrorOutputType::Short => Box::new(ShortEmitter::stderr(None)),
     };
     let handler = errors::Handler::with_emitter(true, false, emitter);
     handler.emit(&MultiSpan::new(), msg, errors::Level::Warning);
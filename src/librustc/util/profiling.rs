@@ -0,0 +1,169 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Self-profiling of the compiler with `-Z self-profile`.
+//!
+//! While profiling, every region passed to `record` is timed, along with the
+//! thread it ran on. At the end of the compilation the regions are written to
+//! `<crate>.trace.json` in the trace event format of Chrome, which
+//! `chrome://tracing` and other trace viewers can load. The regions are the
+//! passes timed with `time`, the type checking and translation of every item,
+//! and the optimization and code generation of every LLVM module.
+
+use session::Session;
+
+use serialize::json::as_json;
+
+use std::fs::File;
+use std::io::{self, Write, BufWriter};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering, ATOMIC_BOOL_INIT, ATOMIC_USIZE_INIT};
+use std::sync::{Mutex, Once, ONCE_INIT};
+use std::thread;
+use std::time::{Duration, Instant};
+
+static ENABLED: AtomicBool = ATOMIC_BOOL_INIT;
+static NEXT_THREAD_ID: AtomicUsize = ATOMIC_USIZE_INIT;
+static INIT: Once = ONCE_INIT;
+static mut PROFILE: *const Mutex<Profile> = 0 as *const Mutex<Profile>;
+
+thread_local!(static THREAD_ID: usize = NEXT_THREAD_ID.fetch_add(1, Ordering::SeqCst));
+
+struct Profile {
+    start: Instant,
+    events: Vec<Event>,
+    /// The id and name of every thread which recorded an event.
+    threads: Vec<(usize, String)>,
+}
+
+struct Event {
+    name: String,
+    category: &'static str,
+    thread: usize,
+    /// When the region started, relative to the start of profiling.
+    start: Duration,
+    duration: Duration,
+}
+
+fn profile() -> &'static Mutex<Profile> {
+    unsafe {
+        INIT.call_once(|| {
+            let profile = Profile {
+                start: Instant::now(),
+                events: Vec::new(),
+                threads: Vec::new(),
+            };
+            PROFILE = Box::into_raw(Box::new(Mutex::new(profile)));
+        });
+        &*PROFILE
+    }
+}
+
+/// Runs `f`, recording it as a region of `category` called `name()` if the
+/// compiler is being profiled. The name is only computed when it's needed.
+pub fn record<T, N, F>(category: &'static str, name: N, f: F) -> T where
+    N: FnOnce() -> String,
+    F: FnOnce() -> T,
+{
+    if !ENABLED.load(Ordering::Relaxed) {
+        return f();
+    }
+
+    let start = Instant::now();
+    let rv = f();
+    let duration = start.elapsed();
+
+    let thread = THREAD_ID.with(|id| *id);
+    let name = name();
+    let mut profile = profile().lock().unwrap();
+    if !profile.threads.iter().any(|&(id, _)| id == thread) {
+        let thread_name = match thread::current().name() {
+            Some(name) => name.to_string(),
+            None => format!("thread {}", thread),
+        };
+        profile.threads.push((thread, thread_name));
+    }
+    let start = start.duration_since(profile.start);
+    profile.events.push(Event {
+        name: name,
+        category: category,
+        thread: thread,
+        start: start,
+        duration: duration,
+    });
+
+    rv
+}
+
+/// Profiles the compiler until it's dropped, and then writes the profile.
+pub struct SelfProfile<'a> {
+    sess: &'a Session,
+    path: Option<PathBuf>,
+}
+
+impl<'a> SelfProfile<'a> {
+    pub fn start(sess: &'a Session) -> SelfProfile<'a> {
+        {
+            let mut profile = profile().lock().unwrap();
+            profile.start = Instant::now();
+            profile.events.clear();
+        }
+        ENABLED.store(true, Ordering::SeqCst);
+        SelfProfile {
+            sess: sess,
+            path: None,
+        }
+    }
+
+    /// Sets the file the profile is written to. Nothing is written if the
+    /// compilation stops before the file is known.
+    pub fn set_output(&mut self, path: PathBuf) {
+        self.path = Some(path);
+    }
+
+    fn write(path: &Path, profile: &Profile) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        write!(out, "{{\"traceEvents\":[")?;
+        for &(id, ref name) in profile.threads.iter() {
+            write!(out, "\n{{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":1,\"tid\":{},\
+                         \"args\":{{\"name\":{}}}}},",
+                   id, as_json(name))?;
+        }
+        for (i, event) in profile.events.iter().enumerate() {
+            if i > 0 {
+                write!(out, ",")?;
+            }
+            write!(out, "\n{{\"name\":{},\"cat\":\"{}\",\"ph\":\"X\",\"pid\":1,\"tid\":{},\
+                         \"ts\":{:.3},\"dur\":{:.3}}}",
+                   as_json(&event.name), event.category, event.thread,
+                   micros(event.start), micros(event.duration))?;
+        }
+        write!(out, "\n]}}\n")
+    }
+}
+
+impl<'a> Drop for SelfProfile<'a> {
+    fn drop(&mut self) {
+        ENABLED.store(false, Ordering::SeqCst);
+        let path = match self.path {
+            Some(ref path) => path,
+            None => return,
+        };
+        let profile = profile().lock().unwrap();
+        if let Err(e) = SelfProfile::write(path, &profile) {
+            self.sess.warn(&format!("failed to write the profile to `{}`: {}",
+                                    path.display(), e));
+        }
+    }
+}
+
+fn micros(d: Duration) -> f64 {
+    d.as_secs() as f64 * 1_000_000.0 + d.subsec_nanos() as f64 / 1_000.0
+}
@@ -23,6 +23,7 @@ use rustc::middle::{self, dependency_format, stability, reachable};
 use rustc::middle::privacy::AccessLevels;
 use rustc::ty::{self, TyCtxt};
 use rustc::util::common::time;
+use rustc::util::profiling::SelfProfile;
 use rustc::util::nodemap::NodeSet;
 use rustc_back::sha2::{Sha256, Digest};
 use rustc_borrowck as borrowck;
@@ -85,6 +86,13 @@ pub fn compile_input(sess: &Session,
         }}
     }
 
+    // Written out when it goes out of scope, whichever way compilation ends.
+    let mut self_profile = if sess.opts.debugging_opts.self_profile {
+        Some(SelfProfile::start(sess))
+    } else {
+        None
+    };
+
     // We need nested scopes here, because the intermediate results can keep
     // large chunks of memory alive and we want to free them as soon as
     // possible to keep the peak memory usage low
@@ -113,6 +121,9 @@ pub fn compile_input(sess: &Session,
         };
 
         let outputs = build_output_filenames(input, outdir, output, &krate.attrs, sess);
+        if let Some(ref mut self_profile) = self_profile {
+            self_profile.set_output(outputs.with_extension("trace.json"));
+        }
         let crate_name = link::find_crate_name(Some(sess), &krate.attrs, input);
         let ExpansionResult { expanded_crate, defs, analysis, resolutions, mut hir_forest } = {
             phase_2_configure_and_expand(
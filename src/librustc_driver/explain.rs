@@ -0,0 +1,397 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Rendering of the long explanations of error codes for `--explain`, and
+//! checking of the examples they contain.
+//!
+//! The explanations are markdown. On a terminal the headings are printed in
+//! bold, inline code is colored and the Rust code blocks are highlighted and
+//! indented, with the lines hidden by rustdoc left out. Everything else gets
+//! the plain markdown.
+
+use errors::Handler;
+use errors::emitter::EmitterWriter;
+use syntax::codemap::CodeMap;
+use syntax::parse::lexer::{self, Reader};
+use syntax::parse::token;
+use syntax_pos::Pos;
+
+use std::env;
+use std::io::prelude::*;
+use std::io;
+use std::panic;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+const RESET: &'static str = "\x1b[0m";
+const BOLD: &'static str = "\x1b[1m";
+const COMMENT: &'static str = "\x1b[2m";
+const KEYWORD: &'static str = "\x1b[35m";
+const STRING: &'static str = "\x1b[32m";
+const NUMBER: &'static str = "\x1b[36m";
+const MACRO: &'static str = "\x1b[34m";
+const ATTRIBUTE: &'static str = "\x1b[33m";
+const INLINE_CODE: &'static str = "\x1b[33m";
+
+/// The attributes of a code block, as written after its opening fence.
+struct BlockInfo {
+    rust: bool,
+    ignore: bool,
+    compile_fail: bool,
+    test_harness: bool,
+    error_codes: Vec<String>,
+}
+
+impl BlockInfo {
+    /// Parses the attributes the way rustdoc does: blocks without any or
+    /// with only attributes known to rustdoc are Rust code.
+    fn parse(info: &str) -> BlockInfo {
+        let mut block = BlockInfo {
+            rust: true,
+            ignore: false,
+            compile_fail: false,
+            test_harness: false,
+            error_codes: Vec::new(),
+        };
+        let mut seen_rust = false;
+        let mut seen_other = false;
+        let tokens = info.split(|c: char| !(c == '_' || c == '-' || c.is_alphanumeric()));
+        for token in tokens.filter(|t| !t.is_empty()) {
+            match token {
+                "rust" | "should_panic" | "no_run" => seen_rust = true,
+                "ignore" => { block.ignore = true; seen_rust = true; }
+                "compile_fail" => { block.compile_fail = true; seen_rust = true; }
+                "test_harness" => { block.test_harness = true; seen_rust = true; }
+                _ if is_error_code(token) => {
+                    block.error_codes.push(token.to_string());
+                    seen_rust = true;
+                }
+                _ => seen_other = true,
+            }
+        }
+        block.rust = seen_rust || !seen_other;
+        block
+    }
+}
+
+enum Chunk<'a> {
+    Line(&'a str),
+    Code(BlockInfo, Vec<&'a str>),
+}
+
+fn is_error_code(s: &str) -> bool {
+    s.len() == 5 && s.starts_with('E') && s[1..].chars().all(|c| c.is_digit(10))
+}
+
+/// Splits an explanation into the lines outside of code blocks and the code
+/// blocks.
+fn chunks(description: &str) -> Vec<Chunk> {
+    let mut chunks = Vec::new();
+    let mut block = None;
+    for line in description.lines() {
+        let is_fence = line.starts_with("```");
+        block = match block {
+            None if is_fence => Some((BlockInfo::parse(&line[3..]), Vec::new())),
+            None => {
+                chunks.push(Chunk::Line(line));
+                None
+            }
+            Some((info, lines)) => {
+                if is_fence {
+                    chunks.push(Chunk::Code(info, lines));
+                    None
+                } else {
+                    let mut lines = lines;
+                    lines.push(line);
+                    Some((info, lines))
+                }
+            }
+        };
+    }
+    if let Some((info, lines)) = block {
+        chunks.push(Chunk::Code(info, lines));
+    }
+    chunks
+}
+
+/// Lines of Rust code blocks starting with `#` are needed to compile the
+/// example, but left out of the rendered documentation.
+fn is_hidden(line: &str) -> bool {
+    let line = line.trim();
+    line == "#" || line.starts_with("# ")
+}
+
+/// Returns the explanation as plain markdown, without the attributes of the
+/// code blocks.
+pub fn render_plain(description: &str) -> String {
+    description.lines().map(|line| {
+        format!("{}\n", if line.starts_with("```") { "```" } else { line })
+    }).collect()
+}
+
+/// Returns the explanation rendered for the terminal, with colors.
+pub fn render_colored(description: &str) -> String {
+    let mut out = String::new();
+    for chunk in chunks(description) {
+        match chunk {
+            Chunk::Line(line) if line.starts_with('#') => {
+                let heading = line.trim_left_matches('#').trim();
+                out.push_str(&format!("{}{}{}\n", BOLD, heading, RESET));
+            }
+            Chunk::Line(line) => {
+                out.push_str(&render_inline_code(line));
+                out.push('\n');
+            }
+            Chunk::Code(info, lines) => {
+                let code = if info.rust {
+                    let visible = lines.into_iter().filter(|l| !is_hidden(l));
+                    highlight(&visible.collect::<Vec<_>>().join("\n"))
+                } else {
+                    lines.join("\n")
+                };
+                for line in code.lines() {
+                    out.push_str(&format!("    {}\n", line));
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Colors the text between pairs of backticks.
+fn render_inline_code(line: &str) -> String {
+    let parts = line.split('`').collect::<Vec<_>>();
+    // An unpaired backtick is left as is.
+    if parts.len() % 2 == 0 {
+        return line.to_string();
+    }
+    let mut out = String::new();
+    for (i, part) in parts.iter().enumerate() {
+        if i % 2 == 1 {
+            out.push_str(&format!("{}{}{}", INLINE_CODE, part, RESET));
+        } else {
+            out.push_str(part);
+        }
+    }
+    out
+}
+
+/// Highlights Rust code using the tokens of the lexer.
+///
+/// This is a simpler version of the highlighter of rustdoc, which can't be
+/// used here as rustdoc depends on the compiler driver.
+fn highlight(code: &str) -> String {
+    // The lexer panics on many errors instead of returning them, and the
+    // code is left plain then too.
+    panic::catch_unwind(|| highlight_tokens(code)).unwrap_or_else(|_| code.to_string())
+}
+
+fn highlight_tokens(code: &str) -> String {
+    let codemap = CodeMap::new();
+    let emitter = Box::new(EmitterWriter::new(Box::new(io::sink()), None));
+    let handler = Handler::with_emitter(false, false, emitter);
+    let filemap = codemap.new_filemap("<explanation>".to_string(), None, code.to_string());
+    let mut lexer = lexer::StringReader::new(&handler, filemap.clone());
+
+    let mut out = String::new();
+    let mut in_attribute = false;
+    loop {
+        let tas = match lexer.try_next_token() {
+            Ok(tas) => tas,
+            Err(..) => {
+                // Not valid Rust after all, don't highlight it.
+                lexer.emit_fatal_errors();
+                return code.to_string();
+            }
+        };
+        let text = &code[(tas.sp.lo - filemap.start_pos).to_usize()..
+                         (tas.sp.hi - filemap.start_pos).to_usize()];
+        let color = match tas.tok {
+            token::Eof => break,
+            token::Pound => {
+                in_attribute = true;
+                Some(ATTRIBUTE)
+            }
+            token::CloseDelim(token::Bracket) if in_attribute => {
+                in_attribute = false;
+                Some(ATTRIBUTE)
+            }
+            _ if in_attribute => Some(ATTRIBUTE),
+            token::Comment | token::DocComment(..) => Some(COMMENT),
+            token::Literal(token::Integer(..), _) |
+            token::Literal(token::Float(..), _) => Some(NUMBER),
+            token::Literal(..) => Some(STRING),
+            token::Lifetime(..) => Some(KEYWORD),
+            ref tok if tok.is_any_keyword() => Some(KEYWORD),
+            token::Ident(..) if lexer.peek().tok == token::Not => Some(MACRO),
+            _ => None,
+        };
+        match color {
+            Some(color) => out.push_str(&format!("{}{}{}", color, text, RESET)),
+            None => out.push_str(text),
+        }
+    }
+    out
+}
+
+/// Shows `content` through the pager of the user when standard output is a
+/// terminal, and prints it otherwise or if the pager can't be started.
+pub fn show(content: &str) {
+    if !stdout_isatty() {
+        print!("{}", content);
+        return;
+    }
+
+    let pager = env::var_os("PAGER").unwrap_or_else(|| {
+        if cfg!(windows) { "more.com".into() } else { "less".into() }
+    });
+    let mut cmd = Command::new(&pager);
+    // Keep the colors when paging with less.
+    if Path::new(&pager).ends_with("less") {
+        cmd.arg("-R");
+    }
+    let mut child = match cmd.stdin(Stdio::piped()).spawn() {
+        Ok(child) => child,
+        Err(..) => {
+            print!("{}", content);
+            return;
+        }
+    };
+    // The user quitting the pager early closes the pipe, which is fine.
+    let _ = child.stdin.take().unwrap().write_all(content.as_bytes());
+    let _ = child.wait();
+}
+
+#[cfg(unix)]
+pub fn stdout_isatty() -> bool {
+    use libc;
+    unsafe { libc::isatty(libc::STDOUT_FILENO) != 0 }
+}
+
+// The Windows console doesn't understand the escape sequences used for the
+// colors, so the output is always plain there.
+#[cfg(windows)]
+pub fn stdout_isatty() -> bool {
+    false
+}
+
+/// Compiles the Rust examples in the explanation of `code` with `rustc`.
+/// Returns a description of every example which doesn't behave as its code
+/// block states: `compile_fail` examples must fail with the error codes
+/// listed for them, and all other examples must compile.
+pub fn check_examples(rustc: &Path, code: &str, description: &str) -> Vec<String> {
+    let mut failures = Vec::new();
+    let examples = chunks(description).into_iter().filter_map(|chunk| {
+        match chunk {
+            Chunk::Code(info, lines) => if info.rust { Some((info, lines)) } else { None },
+            Chunk::Line(..) => None,
+        }
+    });
+    for (i, (info, lines)) in examples.enumerate() {
+        if info.ignore {
+            continue
+        }
+        let name = format!("{} example {}", code, i + 1);
+        let source = make_test(&lines);
+
+        let mut cmd = Command::new(rustc);
+        cmd.arg("-").arg("--crate-name").arg("explanation_example")
+           .arg("--error-format").arg("short")
+           .arg("-Z").arg("no-trans")
+           .arg("--out-dir").arg(env::temp_dir());
+        if info.test_harness {
+            cmd.arg("--test");
+        }
+        let output = cmd.stdin(Stdio::piped())
+                        .stdout(Stdio::piped())
+                        .stderr(Stdio::piped())
+                        .spawn()
+                        .and_then(|mut child| {
+                            child.stdin.take().unwrap().write_all(source.as_bytes())?;
+                            child.wait_with_output()
+                        });
+        let output = match output {
+            Ok(output) => output,
+            Err(e) => {
+                failures.push(format!("{}: failed to run `{}`: {}", name, rustc.display(), e));
+                continue
+            }
+        };
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        match (output.status.success(), info.compile_fail) {
+            (true, true) => {
+                failures.push(format!("{}: compiled, but is marked `compile_fail`", name));
+            }
+            (false, false) => {
+                let error = stderr.lines().next().unwrap_or("");
+                failures.push(format!("{}: failed to compile: {}", name, error));
+            }
+            (false, true) => {
+                for error_code in info.error_codes.iter() {
+                    if !stderr.contains(&format!("[{}]", error_code)) {
+                        failures.push(format!("{}: didn't fail with {}", name, error_code));
+                    }
+                }
+            }
+            (true, false) => {}
+        }
+    }
+    failures
+}
+
+/// Turns the lines of an example into a crate, the way rustdoc does: hidden
+/// lines are included, and the code is wrapped in a `main` function unless
+/// it has one, keeping the crate attributes outside of it.
+fn make_test(lines: &[&str]) -> String {
+    let lines = lines.iter().map(|&line| {
+        let trimmed = line.trim();
+        if trimmed == "#" {
+            ""
+        } else if trimmed.starts_with("# ") {
+            &trimmed[2..]
+        } else {
+            line
+        }
+    }).collect::<Vec<_>>();
+
+    let mut test = String::from("#![allow(unused)]\n");
+    if lines.iter().any(|line| line.contains("fn main")) {
+        test.push_str(&lines.join("\n"));
+    } else {
+        let (attrs, code): (Vec<&str>, Vec<&str>) = lines.into_iter().partition(|line| {
+            line.trim().starts_with("#![")
+        });
+        test.push_str(&attrs.join("\n"));
+        test.push_str("\nfn main() {\n");
+        test.push_str(&code.join("\n"));
+        test.push_str("\n}");
+    }
+    test.push('\n');
+    test
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render_colored, KEYWORD, RESET};
+
+    #[test]
+    fn rust_blocks_are_highlighted() {
+        assert_eq!(render_colored("```\nfn f() {}\n```\n"),
+                   format!("    {}fn{} f() {{}}\n", KEYWORD, RESET));
+    }
+
+    #[test]
+    fn blocks_which_do_not_lex_are_left_plain() {
+        assert_eq!(render_colored("```\n\"unterminated\n```\n"), "    \"unterminated\n");
+        assert_eq!(render_colored("```\nfn f() {}\n\"unterminated\n```\n"),
+                   "    fn f() {}\n    \"unterminated\n");
+    }
+}
@@ -99,12 +99,14 @@ use syntax::codemap::{CodeMap, FileLoader, RealFileLoader};
 use syntax::feature_gate::{GatedCfg, UnstableFeatures};
 use syntax::parse::{self, PResult};
 use syntax_pos::MultiSpan;
+use errors::ColorConfig;
 use errors::emitter::Emitter;
 
 #[cfg(test)]
 pub mod test;
 
 pub mod driver;
+mod explain;
 pub mod pretty;
 pub mod target_features;
 
@@ -123,8 +125,8 @@ fn abort_msg(err_count: usize) -> String {
 
 pub fn abort_on_err<T>(result: Result<T, usize>, sess: &Session) -> T {
     match result {
-        Err(err_count) => {
-            sess.fatal(&abort_msg(err_count));
+        Err(..) => {
+            sess.fatal(&sess.diagnostic().summarize_errors());
         }
         Ok(x) => x,
     }
@@ -136,7 +138,7 @@ pub fn run(args: Vec<String>) -> isize {
         if let Err(err_count) = result {
             if err_count > 0 {
                 match session {
-                    Some(sess) => sess.fatal(&abort_msg(err_count)),
+                    Some(sess) => sess.fatal(&sess.diagnostic().summarize_errors()),
                     None => {
                         let emitter =
                             errors::emitter::EmitterWriter::stderr(errors::ColorConfig::Auto,
@@ -354,24 +356,37 @@ pub trait CompilerCalls<'a> {
 #[derive(Copy, Clone)]
 pub struct RustcDefaultCalls;
 
-fn handle_explain(code: &str,
-                  descriptions: &errors::registry::Registry,
-                  output: ErrorOutputType) {
-    let normalised = if code.starts_with("E") {
+fn normalise_error_code(code: &str) -> String {
+    if code.starts_with("E") {
         code.to_string()
     } else {
         format!("E{0:0>4}", code)
-    };
+    }
+}
+
+fn handle_explain(code: &str,
+                  descriptions: &errors::registry::Registry,
+                  output: ErrorOutputType) {
+    let normalised = normalise_error_code(code);
     match descriptions.find_description(&normalised) {
         Some(ref description) => {
-            // Slice off the leading newline and print.
-            print!("{}", &(&description[1..]).split("\n").map(|x| {
-                format!("{}\n", if x.starts_with("```") {
-                    "```"
-                } else {
-                    x
-                })
-            }).collect::<String>());
+            let color_config = match output {
+                ErrorOutputType::HumanReadable(color_config) |
+                ErrorOutputType::Json(color_config) => color_config,
+                ErrorOutputType::Short => ColorConfig::Never,
+            };
+            let color = match color_config {
+                ColorConfig::Always => true,
+                ColorConfig::Never => false,
+                ColorConfig::Auto => explain::stdout_isatty(),
+            };
+            // Slice off the leading newline.
+            let description = &description[1..];
+            if color {
+                explain::show(&explain::render_colored(description));
+            } else {
+                explain::show(&explain::render_plain(description));
+            }
         }
         None => {
             early_error(output, &format!("no extended information for {}", code));
@@ -379,13 +394,52 @@ fn handle_explain(code: &str,
     }
 }
 
+/// Compiles the examples in the explanation of `code`, or of all error codes,
+/// and fails if any of them doesn't behave as its code block states.
+fn check_explanations(code: Option<&str>,
+                      descriptions: &errors::registry::Registry,
+                      output: ErrorOutputType) {
+    let codes = match code {
+        Some(code) => vec![normalise_error_code(code)],
+        None => descriptions.codes().into_iter().map(|c| c.to_string()).collect(),
+    };
+    let rustc = env::current_exe().unwrap_or_else(|e| {
+        early_error(output, &format!("couldn't find the compiler executable: {}", e))
+    });
+
+    let mut failures = Vec::new();
+    for code in codes.iter() {
+        match descriptions.find_description(code) {
+            Some(description) => {
+                failures.extend(explain::check_examples(&rustc, code, description));
+            }
+            None => {
+                early_error(output, &format!("no extended information for {}", code));
+            }
+        }
+    }
+
+    for failure in failures.iter() {
+        println!("{}", failure);
+    }
+    println!("explanations checked: {}; examples failing: {}", codes.len(), failures.len());
+    if !failures.is_empty() {
+        early_error(output, "some explanations have examples which don't behave as stated");
+    }
+}
+
 fn check_cfg(cfg: &ast::CrateConfig,
              output: ErrorOutputType) {
     let emitter: Box<Emitter> = match output {
         config::ErrorOutputType::HumanReadable(color_config) => {
             Box::new(errors::emitter::EmitterWriter::stderr(color_config, None))
         }
-        config::ErrorOutputType::Json => Box::new(json::JsonEmitter::basic()),
+        config::ErrorOutputType::Json(color_config) => {
+            Box::new(json::JsonEmitter::basic(color_config))
+        }
+        config::ErrorOutputType::Short => {
+            Box::new(errors::short::ShortEmitter::stderr(None))
+        }
     };
     let handler = errors::Handler::with_emitter(true, false, emitter);
 
@@ -408,11 +462,17 @@ fn check_cfg(cfg: &ast::CrateConfig,
 impl<'a> CompilerCalls<'a> for RustcDefaultCalls {
     fn early_callback(&mut self,
                       matches: &getopts::Matches,
-                      _: &config::Options,
+                      sopts: &config::Options,
                       cfg: &ast::CrateConfig,
                       descriptions: &errors::registry::Registry,
                       output: ErrorOutputType)
                       -> Compilation {
+        if sopts.debugging_opts.check_explanations {
+            let code = matches.opt_str("explain");
+            check_explanations(code.as_ref().map(|c| &c[..]), descriptions, output);
+            return Compilation::Stop;
+        }
+
         if let Some(ref code) = matches.opt_str("explain") {
             handle_explain(code, descriptions, output);
             return Compilation::Stop;
@@ -20,6 +20,7 @@ use styled_buffer::StyledBuffer;
 use std::io::prelude::*;
 use std::io;
 use std::rc::Rc;
+use std::sync::{Arc, Mutex};
 use term;
 
 /// Emitter trait for emitting errors.
@@ -103,6 +104,26 @@ impl EmitterWriter {
                         cm: code_map}
     }
 
+    /// Creates an emitter which appends the rendered diagnostics to `sink`
+    /// instead of printing them, for embedding them in other output formats.
+    /// Colors are written as the escape sequences of the current terminal.
+    pub fn buffered(sink: Arc<Mutex<Vec<u8>>>,
+                    color_config: ColorConfig,
+                    code_map: Option<Rc<CodeMapper>>)
+                    -> EmitterWriter {
+        let terminal = if color_config.use_color() {
+            term::TerminfoTerminal::new(BufferedWriter::with_sink(sink.clone()))
+        } else {
+            None
+        };
+        let dst = match terminal {
+            Some(t) => BufferedTerminal(Box::new(t)),
+            None => Raw(Box::new(BufferedWriter::with_sink(sink))),
+        };
+        EmitterWriter { dst: dst,
+                        cm: code_map}
+    }
+
     fn preprocess_annotations(&self, msp: &MultiSpan) -> Vec<FileWithAnnotatedLines> {
         fn add_annotation_to_file(file_vec: &mut Vec<FileWithAnnotatedLines>,
                                     file: Rc<FileMap>,
@@ -627,7 +648,11 @@ impl EmitterWriter {
                                -> io::Result<()> {
         use std::borrow::Borrow;
 
-        let primary_span = suggestion.msp.primary_span().unwrap();
+        let primary_span = suggestion.substitution_parts
+                                     .iter()
+                                     .map(|part| part.span)
+                                     .min_by_key(|span| span.lo)
+                                     .unwrap();
         if let Some(ref cm) = self.cm {
             let mut buffer = StyledBuffer::new();
 
@@ -639,7 +664,9 @@ impl EmitterWriter {
 
             assert!(!lines.lines.is_empty());
 
-            let complete = suggestion.splice_lines(cm.borrow());
+            // Only the message is shown for suggestions spanning several
+            // files, as they can't be spliced into one snippet.
+            let complete = suggestion.splice_lines(cm.borrow()).unwrap_or(String::new());
 
             // print the suggestion without any line numbers, but leave
             // space for them. This helps with lining up with previous
@@ -815,6 +842,8 @@ pub enum Destination {
 /// processes error simultaneously
 pub struct BufferedWriter {
     buffer: Vec<u8>,
+    /// Where the buffer is flushed to, standard error if `None`.
+    sink: Option<Arc<Mutex<Vec<u8>>>>,
 }
 
 impl BufferedWriter {
@@ -822,7 +851,15 @@ impl BufferedWriter {
     // this function unused on some platforms
     fn _new() -> BufferedWriter {
         BufferedWriter {
-            buffer: vec![]
+            buffer: vec![],
+            sink: None,
+        }
+    }
+
+    fn with_sink(sink: Arc<Mutex<Vec<u8>>>) -> BufferedWriter {
+        BufferedWriter {
+            buffer: vec![],
+            sink: Some(sink),
         }
     }
 }
@@ -835,6 +872,11 @@ impl Write for BufferedWriter {
         Ok(buf.len())
     }
     fn flush(&mut self) -> io::Result<()> {
+        if let Some(ref sink) = self.sink {
+            sink.lock().unwrap().extend_from_slice(&self.buffer);
+            self.buffer.clear();
+            return Ok(());
+        }
         let mut stderr = io::stderr();
         let result = (|| {
             stderr.write_all(&self.buffer)?;
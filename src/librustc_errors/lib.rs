@@ -42,18 +42,20 @@ use self::RenderSpan::*;
 use emitter::{Emitter, EmitterWriter};
 
 use std::cell::{RefCell, Cell};
-use std::{error, fmt};
+use std::collections::{HashMap, HashSet};
+use std::{cmp, error, fmt};
 use std::rc::Rc;
 use std::thread::panicking;
 
 pub mod emitter;
+pub mod short;
 pub mod snippet;
 pub mod registry;
 pub mod styled_buffer;
 mod lock;
 
 use syntax_pos::{BytePos, Loc, FileLinesResult, FileName, MultiSpan, Span, NO_EXPANSION };
-use syntax_pos::{MacroBacktrace};
+use syntax_pos::{MacroBacktrace, SpanLinesError};
 
 #[derive(Clone)]
 pub enum RenderSpan {
@@ -64,15 +66,41 @@ pub enum RenderSpan {
 
     /// A suggestion renders with both with an initial line for the
     /// message, prefixed by file:linenum, followed by a summary
-    /// of hypothetical source code, where each `String` is spliced
-    /// into the lines in place of the code covered by each span.
+    /// of hypothetical source code, where each substitute is spliced
+    /// into the lines in place of the code covered by its span.
     Suggestion(CodeSuggestion),
 }
 
+/// A suggested edit of the source code. All of its substitutions are meant to
+/// be applied together, as a single fix.
 #[derive(Clone)]
 pub struct CodeSuggestion {
-    pub msp: MultiSpan,
-    pub substitutes: Vec<String>,
+    pub substitution_parts: Vec<Substitution>,
+    pub applicability: Applicability,
+}
+
+/// Replacement of the code covered by `span` with `substitute`.
+#[derive(Clone)]
+pub struct Substitution {
+    pub span: Span,
+    pub substitute: String,
+}
+
+/// How confident the compiler is that a suggestion is what the user meant,
+/// which tells tools whether they can apply it without asking.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, RustcEncodable)]
+pub enum Applicability {
+    /// The suggestion is definitely what the user intended, and applying it
+    /// results in valid code.
+    MachineApplicable,
+    /// The suggestion may be what the user intended, but it's uncertain. It
+    /// should result in valid code if applied.
+    MaybeIncorrect,
+    /// The suggestion contains placeholders like `(...)`, and so can't be
+    /// applied as is.
+    HasPlaceholders,
+    /// The applicability of the suggestion is unknown.
+    Unspecified,
 }
 
 pub trait CodeMapper {
@@ -84,8 +112,11 @@ pub trait CodeMapper {
 }
 
 impl CodeSuggestion {
-    /// Returns the assembled code suggestion.
-    pub fn splice_lines(&self, cm: &CodeMapper) -> String {
+    /// Returns the assembled code suggestion, or an error if the
+    /// substitutions are in different files.
+    ///
+    /// The substitutions are assumed not to overlap.
+    pub fn splice_lines(&self, cm: &CodeMapper) -> Result<String, SpanLinesError> {
         use syntax_pos::{CharPos, Loc, Pos};
 
         fn push_trailing(buf: &mut String, line_opt: Option<&str>,
@@ -104,22 +135,19 @@ impl CodeSuggestion {
             }
         }
 
-        let mut primary_spans = self.msp.primary_spans().to_owned();
-
-        assert_eq!(primary_spans.len(), self.substitutes.len());
-        if primary_spans.is_empty() {
-            return format!("");
+        let mut parts = self.substitution_parts.iter().collect::<Vec<_>>();
+        if parts.is_empty() {
+            return Ok(format!(""));
         }
 
-        // Assumption: all spans are in the same file, and all spans
-        // are disjoint. Sort in ascending order.
-        primary_spans.sort_by_key(|sp| sp.lo);
+        // Sort the substitutions in ascending order.
+        parts.sort_by_key(|part| part.span.lo);
 
         // Find the bounding span.
-        let lo = primary_spans.iter().map(|sp| sp.lo).min().unwrap();
-        let hi = primary_spans.iter().map(|sp| sp.hi).min().unwrap();
+        let lo = parts.iter().map(|part| part.span.lo).min().unwrap();
+        let hi = parts.iter().map(|part| part.span.hi).max().unwrap();
         let bounding_span = Span { lo: lo, hi: hi, expn_id: NO_EXPANSION };
-        let lines = cm.span_to_lines(bounding_span).unwrap();
+        let lines = cm.span_to_lines(bounding_span)?;
         assert!(!lines.lines.is_empty());
 
         // To build up the result, we do this for each span:
@@ -138,7 +166,8 @@ impl CodeSuggestion {
         let mut prev_line = fm.get_line(lines.lines[0].line_index);
         let mut buf = String::new();
 
-        for (sp, substitute) in primary_spans.iter().zip(self.substitutes.iter()) {
+        for part in parts {
+            let sp = &part.span;
             let cur_lo = cm.lookup_char_pos(sp.lo);
             if prev_hi.line == cur_lo.line {
                 push_trailing(&mut buf, prev_line, &prev_hi, Some(&cur_lo));
@@ -155,14 +184,14 @@ impl CodeSuggestion {
                     buf.push_str(&cur_line[.. cur_lo.col.to_usize()]);
                 }
             }
-            buf.push_str(substitute);
+            buf.push_str(&part.substitute);
             prev_hi = cm.lookup_char_pos(sp.hi);
             prev_line = fm.get_line(prev_hi.line - 1);
         }
         push_trailing(&mut buf, prev_line, &prev_hi, None);
         // remove trailing newline
         buf.pop();
-        buf
+        Ok(buf)
     }
 }
 
@@ -230,7 +259,9 @@ impl<'a> DiagnosticBuilder<'a> {
             return;
         }
 
-        self.handler.emitter.borrow_mut().emit(&self);
+        if self.handler.should_emit(self) {
+            self.handler.emitter.borrow_mut().emit(&self);
+        }
         self.cancel();
         self.handler.panic_if_treat_err_as_bug();
 
@@ -316,14 +347,40 @@ impl<'a> DiagnosticBuilder<'a> {
     /// Prints out a message with a suggested edit of the code.
     ///
     /// See `diagnostic::RenderSpan::Suggestion` for more information.
-    pub fn span_suggestion<S: Into<MultiSpan>>(&mut self,
-                                               sp: S,
-                                               msg: &str,
-                                               suggestion: String)
-                                               -> &mut DiagnosticBuilder<'a> {
+    pub fn span_suggestion(&mut self,
+                           sp: Span,
+                           msg: &str,
+                           suggestion: String)
+                           -> &mut DiagnosticBuilder<'a> {
+        self.span_suggestion_with_applicability(sp, msg, suggestion, Applicability::Unspecified)
+    }
+    /// Like `span_suggestion`, but also states how confident we are that the
+    /// suggestion is correct, see `Applicability`.
+    pub fn span_suggestion_with_applicability(&mut self,
+                                              sp: Span,
+                                              msg: &str,
+                                              suggestion: String,
+                                              applicability: Applicability)
+                                              -> &mut DiagnosticBuilder<'a> {
+        self.multipart_suggestion(msg, vec![(sp, suggestion)], applicability)
+    }
+    /// Prints out a message with a suggested edit of the code at several
+    /// locations, all of which have to be applied together.
+    pub fn multipart_suggestion(&mut self,
+                                msg: &str,
+                                suggestion: Vec<(Span, String)>,
+                                applicability: Applicability)
+                                -> &mut DiagnosticBuilder<'a> {
+        assert!(!suggestion.is_empty(), "a suggestion needs at least one substitution");
+        let parts = suggestion.into_iter().map(|(span, substitute)| {
+            Substitution {
+                span: span,
+                substitute: substitute,
+            }
+        }).collect();
         self.sub(Level::Help, msg, MultiSpan::new(), Some(Suggestion(CodeSuggestion {
-            msp: sp.into(),
-            substitutes: vec![suggestion],
+            substitution_parts: parts,
+            applicability: applicability,
         })));
         self
     }
@@ -417,6 +474,16 @@ pub struct Handler {
     treat_err_as_bug: bool,
     continue_after_error: Cell<bool>,
     delayed_span_bug: RefCell<Option<(MultiSpan, String)>>,
+    /// The code, message and primary span of every diagnostic emitted so far,
+    /// exact duplicates of them are dropped.
+    emitted_diagnostics: RefCell<HashSet<(Option<String>, String, Option<Span>)>>,
+    /// How many errors of one error code are emitted at most.
+    error_code_limit: Cell<Option<usize>>,
+    /// The number of errors seen so far for each error code.
+    error_code_counts: RefCell<HashMap<String, usize>>,
+    /// The number of errors counted in `err_count` which were dropped by
+    /// `should_emit`.
+    hidden_err_count: Cell<usize>,
 }
 
 impl Handler {
@@ -439,6 +506,10 @@ impl Handler {
             treat_err_as_bug: treat_err_as_bug,
             continue_after_error: Cell::new(true),
             delayed_span_bug: RefCell::new(None),
+            emitted_diagnostics: RefCell::new(HashSet::new()),
+            error_code_limit: Cell::new(None),
+            error_code_counts: RefCell::new(HashMap::new()),
+            hidden_err_count: Cell::new(0),
         }
     }
 
@@ -446,6 +517,65 @@ impl Handler {
         self.continue_after_error.set(continue_after_error);
     }
 
+    /// Limits how many errors of each error code are emitted, at least one.
+    /// The others are only counted and summarized before aborting.
+    pub fn set_error_code_limit(&self, limit: Option<usize>) {
+        self.error_code_limit.set(limit.map(|limit| cmp::max(limit, 1)));
+    }
+
+    /// Decides whether `db` is emitted, recording it if so. Diagnostics are
+    /// dropped when they duplicate one emitted before or when their error
+    /// code is over the limit.
+    fn should_emit(&self, db: &DiagnosticBuilder) -> bool {
+        let key = (db.code.clone(), db.message.clone(), db.span.primary_span());
+        let emit = self.emitted_diagnostics.borrow_mut().insert(key) &&
+            match (db.level, &db.code, self.error_code_limit.get()) {
+                (Error, &Some(ref code), Some(limit)) => {
+                    let mut counts = self.error_code_counts.borrow_mut();
+                    let count = counts.entry(code.clone()).or_insert(0);
+                    *count += 1;
+                    *count <= limit
+                }
+                _ => true,
+            };
+        if !emit && (db.level == Error || db.level == Fatal) {
+            self.hidden_err_count.set(self.hidden_err_count.get() + 1);
+        }
+        emit
+    }
+
+    /// Notes how many errors of each error code were suppressed by the
+    /// error code limit, and returns the message to abort the compilation
+    /// with. Only the errors which were shown are counted in it.
+    pub fn summarize_errors(&self) -> String {
+        self.note_suppressed_errors();
+        match self.err_count.get().saturating_sub(self.hidden_err_count.get()) {
+            0 => "aborting with no errors (maybe a bug?)".to_string(),
+            1 => "aborting due to previous error".to_string(),
+            count => format!("aborting due to {} previous errors", count),
+        }
+    }
+
+    fn note_suppressed_errors(&self) {
+        let limit = match self.error_code_limit.get() {
+            Some(limit) => limit,
+            None => return,
+        };
+        let mut suppressed = self.error_code_counts.borrow().iter()
+            .filter(|&(_, &count)| count > limit)
+            .map(|(code, &count)| (code.clone(), count - limit))
+            .collect::<Vec<_>>();
+        suppressed.sort();
+        for (code, count) in suppressed {
+            let msg = if count == 1 {
+                format!("1 more {} error suppressed", code)
+            } else {
+                format!("{} more {} errors suppressed", count, code)
+            };
+            self.note_without_error(&msg);
+        }
+    }
+
     pub fn struct_dummy<'a>(&'a self) -> DiagnosticBuilder<'a> {
         DiagnosticBuilder::new(self, Level::Cancelled, "")
     }
@@ -659,26 +789,19 @@ impl Handler {
         self.err_count.get() > 0
     }
     pub fn abort_if_errors(&self) {
-        let s;
-        match self.err_count.get() {
-            0 => {
-                let delayed_bug = self.delayed_span_bug.borrow();
-                match *delayed_bug {
-                    Some((ref span, ref errmsg)) => {
-                        self.span_bug(span.clone(), errmsg);
-                    },
-                    _ => {}
-                }
-
-                return;
-            }
-            1 => s = "aborting due to previous error".to_string(),
-            _  => {
-                s = format!("aborting due to {} previous errors",
-                            self.err_count.get());
+        if self.err_count.get() == 0 {
+            let delayed_bug = self.delayed_span_bug.borrow();
+            match *delayed_bug {
+                Some((ref span, ref errmsg)) => {
+                    self.span_bug(span.clone(), errmsg);
+                },
+                _ => {}
             }
+
+            return;
         }
 
+        let s = self.summarize_errors();
         panic!(self.fatal(&s));
     }
     pub fn emit(&self,
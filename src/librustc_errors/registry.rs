@@ -23,4 +23,11 @@ impl Registry {
     pub fn find_description(&self, code: &str) -> Option<&'static str> {
         self.descriptions.get(code).cloned()
     }
+
+    /// Returns the error codes having a description, in order.
+    pub fn codes(&self) -> Vec<&'static str> {
+        let mut codes = self.descriptions.keys().cloned().collect::<Vec<_>>();
+        codes.sort();
+        codes
+    }
 }
@@ -0,0 +1,76 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! An emitter printing every diagnostic on a single line, in the format
+//!
+//! ```text
+//! file:line:col: error[E0308]: mismatched types
+//! ```
+//!
+//! Snippets, labels and child diagnostics are left out, which makes the
+//! output suitable for the quickfix lists of editors and for grepping logs.
+
+use {CodeMapper, DiagnosticBuilder};
+use emitter::Emitter;
+
+use syntax_pos::DUMMY_SP;
+
+use std::io::{self, Write};
+use std::rc::Rc;
+
+pub struct ShortEmitter {
+    dst: Box<Write + Send>,
+    cm: Option<Rc<CodeMapper>>,
+}
+
+impl ShortEmitter {
+    pub fn stderr(code_map: Option<Rc<CodeMapper>>) -> ShortEmitter {
+        ShortEmitter::new(Box::new(io::stderr()), code_map)
+    }
+
+    pub fn new(dst: Box<Write + Send>, code_map: Option<Rc<CodeMapper>>) -> ShortEmitter {
+        ShortEmitter {
+            dst: dst,
+            cm: code_map,
+        }
+    }
+
+    /// Returns the `file:line:col: ` prefix of a diagnostic, which is empty
+    /// for diagnostics without a location.
+    fn location(&self, db: &DiagnosticBuilder) -> String {
+        let span = match db.span.primary_span() {
+            Some(span) if span != DUMMY_SP => span,
+            _ => return String::new(),
+        };
+        match self.cm {
+            Some(ref cm) => {
+                let loc = cm.lookup_char_pos(span.lo);
+                format!("{}:{}:{}: ", loc.file.name, loc.line, loc.col.0 + 1)
+            }
+            None => String::new(),
+        }
+    }
+}
+
+impl Emitter for ShortEmitter {
+    fn emit(&mut self, db: &DiagnosticBuilder) {
+        let code = match db.code {
+            Some(ref code) => format!("[{}]", code),
+            None => String::new(),
+        };
+        // Multi-line messages are joined so each diagnostic stays on one line.
+        let message = db.message.lines().collect::<Vec<_>>().join(" ");
+        let location = self.location(db);
+        if let Err(e) = writeln!(&mut self.dst, "{}{}{}: {}",
+                                 location, db.level, code, message) {
+            panic!("failed to print diagnostics: {:?}", e);
+        }
+    }
+}
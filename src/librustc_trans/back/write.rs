@@ -21,6 +21,7 @@ use {CrateTranslation, ModuleLlvm, ModuleSource, ModuleTranslation};
 use util::common::time;
 use util::common::path2cstr;
 use util::fs::link_or_copy;
+use util::profiling;
 use errors::{self, Handler, Level, DiagnosticBuilder};
 use errors::emitter::Emitter;
 use syntax_pos::MultiSpan;
@@ -956,11 +957,14 @@ fn execute_work_item(cgcx: &CodegenContext,
         match work_item.mtrans.source {
             ModuleSource::Translated(mllvm) => {
                 debug!("llvm-optimizing {:?}", work_item.mtrans.name);
-                optimize_and_codegen(cgcx,
-                                     work_item.mtrans,
-                                     mllvm,
-                                     work_item.config,
-                                     work_item.output_names);
+                let name = work_item.mtrans.name.clone();
+                profiling::record("llvm", || format!("optimize and codegen {}", name), || {
+                    optimize_and_codegen(cgcx,
+                                         work_item.mtrans,
+                                         mllvm,
+                                         work_item.config,
+                                         work_item.output_names);
+                });
             }
             ModuleSource::Preexisting(wp) => {
                 let incr_comp_session_dir = cgcx.incr_comp_session_dir
@@ -81,6 +81,7 @@ use value::Value;
 use Disr;
 use util::sha2::Sha256;
 use util::nodemap::{NodeSet, FnvHashMap, FnvHashSet};
+use util::profiling;
 
 use arena::TypedArena;
 use libc::c_uint;
@@ -1712,7 +1713,9 @@ pub fn trans_crate<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
         let trans_items = cgu.items_in_deterministic_order(tcx, &symbol_map);
         tcx.dep_graph.with_task(cgu.work_product_dep_node(), || {
             for (trans_item, _) in trans_items {
-                trans_item.define(&ccx);
+                profiling::record("trans", || trans_item.to_string(tcx), || {
+                    trans_item.define(&ccx);
+                });
             }
 
             // If this codegen unit contains the main function, also create the
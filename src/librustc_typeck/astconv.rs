@@ -73,7 +73,7 @@ use syntax::{abi, ast};
 use syntax::feature_gate::{GateIssue, emit_feature_err};
 use syntax::parse::token::{self, keywords};
 use syntax_pos::{Span, Pos};
-use errors::DiagnosticBuilder;
+use errors::{Applicability, DiagnosticBuilder};
 
 pub trait AstConv<'gcx, 'tcx> {
     fn tcx<'a>(&'a self) -> TyCtxt<'a, 'gcx, 'tcx>;
@@ -1007,20 +1007,26 @@ impl<'o, 'gcx: 'tcx, 'tcx> AstConv<'gcx, 'tcx>+'o {
                 match (&ty.node, full_span) {
                     (&hir::TyRptr(None, ref mut_ty), Some(full_span)) => {
                         let mutbl_str = if mut_ty.mutbl == hir::MutMutable { "mut " } else { "" };
-                        err.span_suggestion(full_span, "try adding parentheses (per RFC 438):",
-                                            format!("&{}({} +{})",
-                                                    mutbl_str,
-                                                    pprust::ty_to_string(&mut_ty.ty),
-                                                    pprust::bounds_to_string(bounds)));
+                        err.span_suggestion_with_applicability(
+                            full_span,
+                            "try adding parentheses (per RFC 438):",
+                            format!("&{}({} +{})",
+                                    mutbl_str,
+                                    pprust::ty_to_string(&mut_ty.ty),
+                                    pprust::bounds_to_string(bounds)),
+                            Applicability::MachineApplicable);
                     }
                     (&hir::TyRptr(Some(ref lt), ref mut_ty), Some(full_span)) => {
                         let mutbl_str = if mut_ty.mutbl == hir::MutMutable { "mut " } else { "" };
-                        err.span_suggestion(full_span, "try adding parentheses (per RFC 438):",
-                                            format!("&{} {}({} +{})",
-                                                    pprust::lifetime_to_string(lt),
-                                                    mutbl_str,
-                                                    pprust::ty_to_string(&mut_ty.ty),
-                                                    pprust::bounds_to_string(bounds)));
+                        err.span_suggestion_with_applicability(
+                            full_span,
+                            "try adding parentheses (per RFC 438):",
+                            format!("&{} {}({} +{})",
+                                    pprust::lifetime_to_string(lt),
+                                    mutbl_str,
+                                    pprust::ty_to_string(&mut_ty.ty),
+                                    pprust::bounds_to_string(bounds)),
+                            Applicability::MachineApplicable);
                     }
 
                     _ => {
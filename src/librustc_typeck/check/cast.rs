@@ -40,6 +40,7 @@
 
 use super::FnCtxt;
 
+use errors::Applicability;
 use lint;
 use hir::def_id::DefId;
 use rustc::hir;
@@ -224,9 +225,11 @@ impl<'a, 'gcx, 'tcx> CastCheck<'tcx> {
                 if self.cast_ty.is_trait() {
                     match fcx.tcx.sess.codemap().span_to_snippet(self.cast_span) {
                         Ok(s) => {
-                            err.span_suggestion(self.cast_span,
-                                                "try casting to a reference instead:",
-                                                format!("&{}{}", mtstr, s));
+                            err.span_suggestion_with_applicability(
+                                self.cast_span,
+                                "try casting to a reference instead:",
+                                format!("&{}{}", mtstr, s),
+                                Applicability::MaybeIncorrect);
                         },
                         Err(_) =>
                             span_help!(err, self.cast_span,
@@ -241,9 +244,11 @@ impl<'a, 'gcx, 'tcx> CastCheck<'tcx> {
             ty::TyBox(..) => {
                 match fcx.tcx.sess.codemap().span_to_snippet(self.cast_span) {
                     Ok(s) => {
-                        err.span_suggestion(self.cast_span,
-                                            "try casting to a `Box` instead:",
-                                            format!("Box<{}>", s));
+                        err.span_suggestion_with_applicability(
+                            self.cast_span,
+                            "try casting to a `Box` instead:",
+                            format!("Box<{}>", s),
+                            Applicability::MaybeIncorrect);
                     },
                     Err(_) =>
                         span_help!(err, self.cast_span, "did you mean `Box<{}>`?", tstr),
@@ -105,6 +105,7 @@ use TypeAndSubsts;
 use lint;
 use util::common::{block_query, ErrorReported, indenter, loop_query};
 use util::nodemap::{DefIdMap, FnvHashMap, FnvHashSet, NodeMap};
+use util::profiling;
 
 use std::cell::{Cell, Ref, RefCell};
 use std::mem::replace;
@@ -118,7 +119,7 @@ use syntax::parse::token::{self, InternedString, keywords};
 use syntax::ptr::P;
 use syntax::util::lev_distance::find_best_match_for_name;
 use syntax_pos::{self, Span};
-use errors::DiagnosticBuilder;
+use errors::{Applicability, DiagnosticBuilder};
 
 use rustc::hir::intravisit::{self, Visitor};
 use rustc::hir::{self, PatKind};
@@ -464,7 +465,10 @@ impl<'a, 'tcx> Visitor<'tcx> for CheckItemTypesVisitor<'a, 'tcx> {
 
 impl<'a, 'tcx> Visitor<'tcx> for CheckItemBodiesVisitor<'a, 'tcx> {
     fn visit_item(&mut self, i: &'tcx hir::Item) {
-        check_item_body(self.ccx, i);
+        let tcx = self.ccx.tcx;
+        profiling::record("typeck", || tcx.item_path_str(tcx.map.local_def_id(i.id)), || {
+            check_item_body(self.ccx, i);
+        });
     }
 }
 
@@ -3745,11 +3749,12 @@ impl<'a, 'gcx, 'tcx> FnCtxt<'a, 'gcx, 'tcx> {
                                             ast::LitIntType::Unsuffixed) = lit.node {
                                       let snip = tcx.sess.codemap().span_to_snippet(base.span);
                                       if let Ok(snip) = snip {
-                                          err.span_suggestion(expr.span,
-                                                              "to access tuple elements, \
-                                                               use tuple indexing syntax \
-                                                               as shown",
-                                                              format!("{}.{}", snip, i));
+                                          err.span_suggestion_with_applicability(
+                                              expr.span,
+                                              "to access tuple elements, use tuple \
+                                               indexing syntax as shown",
+                                              format!("{}.{}", snip, i),
+                                              Applicability::MachineApplicable);
                                           needs_note = false;
                                       }
                                   }
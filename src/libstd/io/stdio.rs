@@ -26,6 +26,14 @@ thread_local! {
     }
 }
 
+/// Buffer capturing the output of print! and panics, used by the test
+/// harness. Unlike `LOCAL_STDOUT` it is inherited by spawned threads.
+thread_local! {
+    static OUTPUT_CAPTURE: RefCell<Option<Arc<Mutex<Vec<u8>>>>> = {
+        RefCell::new(None)
+    }
+}
+
 /// A handle to a raw instance of the standard input stream of this process.
 ///
 /// This handle is not synchronized or buffered in any fashion. Constructed via
@@ -627,6 +635,55 @@ pub fn set_print(sink: Box<Write + Send>) -> Option<Box<Write + Send>> {
     })
 }
 
+/// Sets the buffer capturing the output of the current thread
+///
+/// While it is set, `print!`, `println!` and the messages of panics of this
+/// thread go to the buffer instead of stdout and stderr, unless redirected
+/// with `set_print` or `set_panic`. Threads spawned by this thread start out
+/// capturing to the same buffer. Returns the previous buffer.
+#[unstable(feature = "set_stdio",
+           reason = "this function may disappear completely or be replaced \
+                     with a more general mechanism",
+           issue = "0")]
+#[doc(hidden)]
+pub fn set_output_capture(sink: Option<Arc<Mutex<Vec<u8>>>>) -> Option<Arc<Mutex<Vec<u8>>>> {
+    use mem;
+    OUTPUT_CAPTURE.with(move |slot| {
+        mem::replace(&mut *slot.borrow_mut(), sink)
+    })
+}
+
+/// Returns the buffer capturing the output of the current thread, if any
+#[unstable(feature = "set_stdio",
+           reason = "this function may disappear completely or be replaced \
+                     with a more general mechanism",
+           issue = "0")]
+#[doc(hidden)]
+pub fn output_capture() -> Option<Arc<Mutex<Vec<u8>>>> {
+    match OUTPUT_CAPTURE.state() {
+        LocalKeyState::Valid => OUTPUT_CAPTURE.with(|s| s.borrow().clone()),
+        LocalKeyState::Uninitialized |
+        LocalKeyState::Destroyed => None,
+    }
+}
+
+// Writes to the output capture of the current thread if there is one, and to
+// stdout otherwise.
+fn print_to_capture_or_stdout(args: fmt::Arguments) -> io::Result<()> {
+    match output_capture() {
+        Some(capture) => {
+            // Formatting runs arbitrary code which may panic, and the panic
+            // message goes to the capture too, so it's not locked until the
+            // output is formatted.
+            let mut output = Vec::new();
+            try!(output.write_fmt(args));
+            let mut capture = capture.lock().unwrap_or_else(|e| e.into_inner());
+            capture.write_all(&output)
+        }
+        None => stdout().write_fmt(args),
+    }
+}
+
 #[unstable(feature = "print",
            reason = "implementation detail which may disappear or be replaced at any time",
            issue = "0")]
@@ -645,7 +702,7 @@ pub fn _print(args: fmt::Arguments) {
     // If, however, the actual I/O causes an error, we do indeed panic.
     let result = match LOCAL_STDOUT.state() {
         LocalKeyState::Uninitialized |
-        LocalKeyState::Destroyed => stdout().write_fmt(args),
+        LocalKeyState::Destroyed => print_to_capture_or_stdout(args),
         LocalKeyState::Valid => {
             LOCAL_STDOUT.with(|s| {
                 if s.borrow_state() == BorrowState::Unused {
@@ -653,7 +710,7 @@ pub fn _print(args: fmt::Arguments) {
                         return w.write_fmt(args);
                     }
                 }
-                stdout().write_fmt(args)
+                print_to_capture_or_stdout(args)
             })
         }
     };
@@ -664,9 +721,43 @@ pub fn _print(args: fmt::Arguments) {
 
 #[cfg(test)]
 mod tests {
+    use fmt;
+    use sync::{Arc, Mutex};
     use thread;
     use super::*;
 
+    #[test]
+    fn output_capture_is_inherited_by_spawned_threads() {
+        let capture = Arc::new(Mutex::new(Vec::new()));
+        let prev = set_output_capture(Some(capture.clone()));
+        thread::spawn(|| {
+            print!("child");
+            thread::spawn(|| print!(" and grandchild")).join().unwrap();
+        }).join().unwrap();
+        set_output_capture(prev);
+        assert_eq!(&capture.lock().unwrap()[..], &b"child and grandchild"[..]);
+    }
+
+    #[test]
+    fn panic_while_printing_goes_to_output_capture() {
+        struct Bomb;
+        impl fmt::Display for Bomb {
+            fn fmt(&self, _: &mut fmt::Formatter) -> fmt::Result {
+                panic!("formatting failed")
+            }
+        }
+
+        let capture = Arc::new(Mutex::new(Vec::new()));
+        let capture2 = capture.clone();
+        let result = thread::spawn(move || {
+            set_output_capture(Some(capture2));
+            println!("{}", Bomb);
+        }).join();
+        assert!(result.is_err());
+        let output = String::from_utf8(capture.lock().unwrap().clone()).unwrap();
+        assert!(output.contains("formatting failed"));
+    }
+
     #[test]
     fn panic_doesnt_poison() {
         thread::spawn(|| {
@@ -230,7 +230,12 @@ fn default_hook(info: &PanicInfo) {
                 *slot.borrow_mut() = s.take();
             });
         }
-        (None, Some(ref mut err)) => { write(err) }
+        (None, Some(ref mut err)) => {
+            match ::io::output_capture() {
+                Some(capture) => write(&mut *capture.lock().unwrap_or_else(|e| e.into_inner())),
+                None => write(err),
+            }
+        }
         _ => {}
     }
 }
@@ -273,10 +273,16 @@ impl Builder {
             = Arc::new(UnsafeCell::new(None));
         let their_packet = my_packet.clone();
 
+        // Output captured by the test harness is captured for spawned threads too
+        let output_capture = io::output_capture();
+
         let main = move || {
             if let Some(name) = their_thread.cname() {
                 imp::Thread::set_name(name);
             }
+            if output_capture.is_some() {
+                io::set_output_capture(output_capture);
+            }
             unsafe {
                 thread_info::set(imp::guard::current(), their_thread);
                 let try_result = panic::catch_unwind(panic::AssertUnwindSafe(f));
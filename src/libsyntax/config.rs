@@ -285,5 +285,5 @@ fn is_cfg(attr: &ast::Attribute) -> bool {
 }
 
 fn is_test_or_bench(attr: &ast::Attribute) -> bool {
-    attr.check_name("test") || attr.check_name("bench")
+    attr.check_name("test") || attr.check_name("bench") || attr.check_name("test_cases")
 }
@@ -288,7 +288,10 @@ declare_features! (
     (active, abi_sysv64, "1.13.0", Some(36167)),
 
     // Use the import semantics from RFC 1560.
-    (active, item_like_imports, "1.13.0", Some(35120))
+    (active, item_like_imports, "1.13.0", Some(35120)),
+
+    // Allows `#[test_cases]` functions generating test cases at runtime.
+    (active, test_cases, "1.13.0", None)
 );
 
 declare_features! (
@@ -397,6 +400,9 @@ pub const KNOWN_ATTRIBUTES: &'static [(&'static str, AttributeType, AttributeGat
     ("start", Normal, Ungated),
     ("test", Normal, Ungated),
     ("bench", Normal, Ungated),
+    ("test_cases", Normal, Gated("test_cases",
+                                 "`#[test_cases]` is an experimental feature",
+                                 cfg_fn!(test_cases))),
     ("simd", Normal, Ungated),
     ("repr", Normal, Ungated),
     ("path", Normal, Ungated),
@@ -23,10 +23,12 @@ use codemap::CodeMap;
 use syntax_pos::{self, MacroBacktrace, Span, SpanLabel, MultiSpan};
 use errors::registry::Registry;
 use errors::{DiagnosticBuilder, SubDiagnostic, RenderSpan, CodeSuggestion, CodeMapper};
-use errors::emitter::Emitter;
+use errors::Applicability;
+use errors::emitter::{ColorConfig, Emitter, EmitterWriter};
 
 use std::rc::Rc;
 use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
 use std::vec;
 
 use rustc_serialize::json::as_json;
@@ -35,19 +37,23 @@ pub struct JsonEmitter {
     dst: Box<Write + Send>,
     registry: Option<Registry>,
     cm: Rc<CodeMapper + 'static>,
+    /// Whether the human-readable rendering of the diagnostics is colored.
+    color_config: ColorConfig,
 }
 
 impl JsonEmitter {
-    pub fn basic() -> JsonEmitter {
-        JsonEmitter::stderr(None, Rc::new(CodeMap::new()))
+    pub fn basic(color_config: ColorConfig) -> JsonEmitter {
+        JsonEmitter::stderr(None, Rc::new(CodeMap::new()), color_config)
     }
 
     pub fn stderr(registry: Option<Registry>,
-                  code_map: Rc<CodeMap>) -> JsonEmitter {
+                  code_map: Rc<CodeMap>,
+                  color_config: ColorConfig) -> JsonEmitter {
         JsonEmitter {
             dst: Box::new(io::stderr()),
             registry: registry,
             cm: code_map,
+            color_config: color_config,
         }
     }
 }
@@ -73,9 +79,10 @@ struct Diagnostic<'a> {
     spans: Vec<DiagnosticSpan>,
     /// Associated diagnostic messages.
     children: Vec<Diagnostic<'a>>,
-    /// The message as rustc would render it. Currently this is only
-    /// `Some` for "suggestions", but eventually it will include all
-    /// snippets.
+    /// The message as rustc would render it. For a top-level diagnostic
+    /// this is the complete human-readable output, including snippets and
+    /// children. For children it is only `Some` for "suggestions", holding
+    /// the suggested code.
     rendered: Option<String>,
 }
 
@@ -100,8 +107,14 @@ struct DiagnosticSpan {
     /// If we are suggesting a replacement, this will contain text
     /// that should be sliced in atop this span. You may prefer to
     /// load the fully rendered version from the parent `Diagnostic`,
-    /// however.
+    /// however. All the spans of a diagnostic with a suggested
+    /// replacement form a single suggestion, and have to be replaced
+    /// together.
     suggested_replacement: Option<String>,
+    /// If the suggestion is approximate or contains placeholders, this
+    /// says so, see `errors::Applicability`. Tools should only apply
+    /// suggestions automatically if this is "MachineApplicable".
+    suggestion_applicability: Option<Applicability>,
     /// Macro invocations that created the code at this span, if any.
     expansion: Option<Box<DiagnosticSpanMacroExpansion>>,
 }
@@ -150,7 +163,7 @@ impl<'a> Diagnostic<'a> {
             children: db.children.iter().map(|c| {
                 Diagnostic::from_sub_diagnostic(c, je)
             }).collect(),
-            rendered: None,
+            rendered: Some(je.render_diagnostic(db)),
         }
     }
 
@@ -171,7 +184,7 @@ impl<'a> Diagnostic<'a> {
 
 impl DiagnosticSpan {
     fn from_span_label(span: SpanLabel,
-                       suggestion: Option<&String>,
+                       suggestion: Option<(&String, Applicability)>,
                        je: &JsonEmitter)
                        -> DiagnosticSpan {
         Self::from_span_etc(span.span,
@@ -184,7 +197,7 @@ impl DiagnosticSpan {
     fn from_span_etc(span: Span,
                      is_primary: bool,
                      label: Option<String>,
-                     suggestion: Option<&String>,
+                     suggestion: Option<(&String, Applicability)>,
                      je: &JsonEmitter)
                      -> DiagnosticSpan {
         // obtain the full backtrace from the `macro_backtrace`
@@ -204,7 +217,7 @@ impl DiagnosticSpan {
     fn from_span_full(span: Span,
                       is_primary: bool,
                       label: Option<String>,
-                      suggestion: Option<&String>,
+                      suggestion: Option<(&String, Applicability)>,
                       mut backtrace: vec::IntoIter<MacroBacktrace>,
                       je: &JsonEmitter)
                       -> DiagnosticSpan {
@@ -242,7 +255,8 @@ impl DiagnosticSpan {
             column_end: end.col.0 + 1,
             is_primary: is_primary,
             text: DiagnosticSpanLine::from_span(span, je),
-            suggested_replacement: suggestion.cloned(),
+            suggested_replacement: suggestion.map(|x| x.0.clone()),
+            suggestion_applicability: suggestion.map(|x| x.1),
             expansion: backtrace_step,
             label: label,
         }
@@ -257,16 +271,17 @@ impl DiagnosticSpan {
 
     fn from_suggestion(suggestion: &CodeSuggestion, je: &JsonEmitter)
                        -> Vec<DiagnosticSpan> {
-        assert_eq!(suggestion.msp.span_labels().len(), suggestion.substitutes.len());
-        suggestion.msp.span_labels()
-                      .into_iter()
-                      .zip(&suggestion.substitutes)
-                      .map(|(span_label, suggestion)| {
-                          DiagnosticSpan::from_span_label(span_label,
-                                                          Some(suggestion),
-                                                          je)
-                      })
-                      .collect()
+        suggestion.substitution_parts
+                  .iter()
+                  .map(|part| {
+                      DiagnosticSpan::from_span_etc(part.span,
+                                                    true,
+                                                    None,
+                                                    Some((&part.substitute,
+                                                          suggestion.applicability)),
+                                                    je)
+                  })
+                  .collect()
     }
 
     fn from_render_span(rsp: &RenderSpan, je: &JsonEmitter) -> Vec<DiagnosticSpan> {
@@ -330,6 +345,14 @@ impl DiagnosticCode {
 }
 
 impl JsonEmitter {
+    /// Renders `db` the way the `EmitterWriter` would print it.
+    fn render_diagnostic(&self, db: &DiagnosticBuilder) -> String {
+        let buf = Arc::new(Mutex::new(Vec::new()));
+        EmitterWriter::buffered(buf.clone(), self.color_config, Some(self.cm.clone())).emit(db);
+        let rendered = buf.lock().unwrap();
+        String::from_utf8_lossy(&rendered).into_owned()
+    }
+
     fn render(&self, render_span: &RenderSpan) -> Option<String> {
         use std::borrow::Borrow;
 
@@ -338,9 +361,71 @@ impl JsonEmitter {
                 None
             }
             RenderSpan::Suggestion(ref suggestion) => {
-                Some(suggestion.splice_lines(self.cm.borrow()))
+                suggestion.splice_lines(self.cm.borrow()).ok()
             }
         }
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::JsonEmitter;
+    use codemap::CodeMap;
+    use errors::{Applicability, Handler};
+    use errors::emitter::ColorConfig;
+    use rustc_serialize::json::Json;
+    use syntax_pos::{BytePos, Span, NO_EXPANSION};
+    use std::io::{self, Write};
+    use std::rc::Rc;
+    use std::sync::{Arc, Mutex};
+
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn multipart_suggestions_are_spliced_and_listed() {
+        let src = "fn main() {\n    let x = foo(a, b);\n}\n";
+        let cm = Rc::new(CodeMap::new());
+        let fm = cm.new_filemap_and_lines("test.rs", None, src);
+        // The span of the first `len` bytes of `s`
+        let span_of = |s: &str, len: u32| {
+            let lo = fm.start_pos + BytePos(src.find(s).unwrap() as u32);
+            Span { lo: lo, hi: lo + BytePos(len), expn_id: NO_EXPANSION }
+        };
+
+        let out = Arc::new(Mutex::new(Vec::new()));
+        let emitter = JsonEmitter {
+            dst: Box::new(Shared(out.clone())),
+            registry: None,
+            cm: cm.clone(),
+            color_config: ColorConfig::Never,
+        };
+        let handler = Handler::with_emitter(true, false, Box::new(emitter));
+        handler.struct_span_err(span_of("foo(a, b)", 9), "mismatched types")
+               .multipart_suggestion("borrow the arguments",
+                                     vec![(span_of("b)", 1), "&b".to_string()),
+                                          (span_of("a,", 1), "&a".to_string())],
+                                     Applicability::MachineApplicable)
+               .emit();
+
+        let out = out.lock().unwrap();
+        let diagnostic = Json::from_str(&String::from_utf8_lossy(&out)).unwrap();
+        assert!(diagnostic["rendered"].as_string().unwrap()
+                                      .contains("    let x = foo(&a, &b);"));
+        let suggestion = &diagnostic["children"][0];
+        assert_eq!(suggestion["rendered"].as_string(), Some("    let x = foo(&a, &b);"));
+        let replacements = suggestion["spans"].as_array().unwrap().iter().map(|span| {
+            span["suggested_replacement"].as_string().unwrap()
+        }).collect::<Vec<_>>();
+        assert_eq!(replacements, ["&b", "&a"]);
+    }
+}
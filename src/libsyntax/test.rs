@@ -50,6 +50,7 @@ struct Test {
     span: Span,
     path: Vec<ast::Ident> ,
     bench: bool,
+    cases: bool,
     ignore: bool,
     should_panic: ShouldPanic
 }
@@ -118,7 +119,8 @@ impl<'a> fold::Folder for TestHarnessGenerator<'a> {
         }
         debug!("current path: {}", path_name_i(&self.cx.path));
 
-        let i = if is_test_fn(&self.cx, &i) || is_bench_fn(&self.cx, &i) {
+        let i = if is_test_fn(&self.cx, &i) || is_bench_fn(&self.cx, &i) ||
+                   is_test_cases_fn(&self.cx, &i) {
             match i.node {
                 ast::ItemKind::Fn(_, ast::Unsafety::Unsafe, _, _, _, _) => {
                     let diag = self.cx.span_diagnostic;
@@ -130,6 +132,7 @@ impl<'a> fold::Folder for TestHarnessGenerator<'a> {
                         span: i.span,
                         path: self.cx.path.clone(),
                         bench: is_bench_fn(&self.cx, &i),
+                        cases: is_test_cases_fn(&self.cx, &i),
                         ignore: is_ignored(&i),
                         should_panic: should_panic(&i)
                     };
@@ -402,6 +405,34 @@ fn is_bench_fn(cx: &TestCtxt, i: &ast::Item) -> bool {
     return has_bench_attr && has_test_signature(i);
 }
 
+// A `#[test_cases]` function generates test cases at runtime. It returns the
+// cases as `Vec<(String, Box<Fn() + Send>)>`, which typeck checks once the
+// function is referenced from the test harness.
+fn is_test_cases_fn(cx: &TestCtxt, i: &ast::Item) -> bool {
+    let has_test_cases_attr = attr::contains_name(&i.attrs, "test_cases");
+
+    fn has_test_cases_signature(i: &ast::Item) -> bool {
+        match i.node {
+            ast::ItemKind::Fn(ref decl, _, _, _, ref generics, _) => {
+                let has_output = match decl.output {
+                    ast::FunctionRetTy::Default(..) => false,
+                    ast::FunctionRetTy::Ty(ref t) => t.node != ast::TyKind::Tup(vec![]),
+                };
+                decl.inputs.is_empty() && has_output && !generics.is_parameterized()
+            }
+            _ => false
+        }
+    }
+
+    if has_test_cases_attr && !has_test_cases_signature(i) {
+        let diag = cx.span_diagnostic;
+        diag.span_err(i.span, "functions used as test case generators must have signature \
+                      `fn() -> Vec<(String, Box<Fn() + Send>)>`");
+    }
+
+    return has_test_cases_attr && has_test_cases_signature(i);
+}
+
 fn is_ignored(i: &ast::Item) -> bool {
     i.attrs.iter().any(|attr| attr.check_name("ignore"))
 }
@@ -685,7 +716,13 @@ fn mk_test_desc_and_fn_rec(cx: &TestCtxt, test: &Test) -> P<ast::Expr> {
 
     let fn_expr = ecx.expr_path(ecx.path_global(span, visible_path));
 
-    let variant_name = if test.bench { "StaticBenchFn" } else { "StaticTestFn" };
+    let variant_name = if test.bench {
+        "StaticBenchFn"
+    } else if test.cases {
+        "StaticTestCasesFn"
+    } else {
+        "StaticTestFn"
+    };
     // self::test::$variant_name($fn_expr)
     let testfn_expr = ecx.expr_call(span, ecx.expr_path(test_path(variant_name)), vec![fn_expr]);
 
@@ -34,7 +34,6 @@
 #![cfg_attr(not(stage0), deny(warnings))]
 
 #![feature(asm)]
-#![feature(box_syntax)]
 #![feature(fnbox)]
 #![feature(libc)]
 #![feature(rustc_private)]
@@ -50,6 +49,7 @@ extern crate panic_unwind;
 
 pub use self::TestFn::*;
 pub use self::ColorConfig::*;
+pub use self::OutputFormat::*;
 pub use self::TestResult::*;
 pub use self::TestName::*;
 use self::TestEvent::*;
@@ -59,6 +59,7 @@ use self::OutputLocation::*;
 use std::boxed::FnBox;
 
 use std::any::Any;
+use std::cell::Cell;
 use std::cmp;
 use std::collections::BTreeMap;
 use std::env;
@@ -67,20 +68,32 @@ use std::fs::File;
 use std::io::prelude::*;
 use std::io;
 use std::iter::repeat;
-use std::path::PathBuf;
-use std::sync::mpsc::{channel, Sender};
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering, ATOMIC_USIZE_INIT};
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Instant, Duration};
 
 const TEST_WARN_TIMEOUT_S: u64 = 60;
+const BENCH_TIME_S: u64 = 3;
+
+// When a test binary is re-executed to run a single test in isolation, this
+// environment variable holds the name of the test to run.
+const ISOLATED_TEST_ENV: &'static str = "__RUST_TEST_ISOLATED_NAME";
+// Set for isolated tests when the binary was compiled with `-C panic=abort`.
+const ISOLATED_PANIC_ABORTS_ENV: &'static str = "__RUST_TEST_ISOLATED_PANIC_ABORTS";
+// Set when the test binary is re-executed to find out whether panics abort.
+const PANIC_PROBE_ENV: &'static str = "__RUST_TEST_PANIC_PROBE";
 
 // to be used by rustc to compile tests in libtest
 pub mod test {
     pub use {Bencher, TestName, TestResult, TestDesc, TestDescAndFn, TestOpts, TrFailed,
              TrIgnored, TrOk, Metric, MetricMap, StaticTestFn, StaticTestName, DynTestName,
-             DynTestFn, run_test, test_main, test_main_static, filter_tests, parse_opts,
-             StaticBenchFn, ShouldPanic};
+             DynTestFn, StaticTestCasesFn, run_test, test_main, test_main_static, filter_tests,
+             parse_opts, StaticBenchFn, ShouldPanic};
 }
 
 pub mod stats;
@@ -146,6 +159,9 @@ pub enum TestFn {
     DynTestFn(Box<FnBox() + Send>),
     DynMetricFn(Box<FnBox(&mut MetricMap) + Send>),
     DynBenchFn(Box<TDynBenchFn + 'static>),
+    // A `#[test_cases]` function, which generates named test cases when the
+    // tests are collected. See `expand_test_cases`.
+    StaticTestCasesFn(fn() -> Vec<(String, Box<Fn() + Send>)>),
 }
 
 impl TestFn {
@@ -157,6 +173,7 @@ impl TestFn {
             DynTestFn(..) => PadNone,
             DynMetricFn(..) => PadOnRight,
             DynBenchFn(..) => PadOnRight,
+            StaticTestCasesFn(..) => PadNone,
         }
     }
 }
@@ -170,6 +187,7 @@ impl fmt::Debug for TestFn {
             DynTestFn(..) => "DynTestFn(..)",
             DynMetricFn(..) => "DynMetricFn(..)",
             DynBenchFn(..) => "DynBenchFn(..)",
+            StaticTestCasesFn(..) => "StaticTestCasesFn(..)",
         })
     }
 }
@@ -183,6 +201,8 @@ impl fmt::Debug for TestFn {
 pub struct Bencher {
     iterations: u64,
     dur: Duration,
+    warm_up: Duration,
+    max_time: Duration,
     pub bytes: u64,
 }
 
@@ -243,6 +263,12 @@ impl Clone for MetricMap {
 // The default console test runner. It accepts the command line
 // arguments and a vector of test_descs.
 pub fn test_main(args: &[String], tests: Vec<TestDescAndFn>) {
+    if env::var_os(PANIC_PROBE_ENV).is_some() {
+        probe_panic_strategy();
+    }
+    if let Ok(name) = env::var(ISOLATED_TEST_ENV) {
+        run_isolated_test(&name, tests);
+    }
     let opts = match parse_opts(args) {
         Some(Ok(o)) => o,
         Some(Err(msg)) => panic!("{:?}", msg),
@@ -279,6 +305,12 @@ pub fn test_main_static(tests: &[TestDescAndFn]) {
                                            desc: t.desc.clone(),
                                        }
                                    }
+                                   StaticTestCasesFn(f) => {
+                                       TestDescAndFn {
+                                           testfn: StaticTestCasesFn(f),
+                                           desc: t.desc.clone(),
+                                       }
+                                   }
                                    _ => panic!("non-static tests passed to test::test_main_static"),
                                }
                            })
@@ -293,31 +325,71 @@ pub enum ColorConfig {
     NeverColor,
 }
 
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum OutputFormat {
+    PrettyFormat,
+    JsonFormat,
+}
+
 pub struct TestOpts {
-    pub filter: Option<String>,
+    pub filters: Vec<String>,
+    pub filter_exact: bool,
+    pub skip: Vec<String>,
+    pub list: bool,
+    pub shard: Option<(usize, usize)>,
+    pub shuffle_seed: Option<u64>,
     pub run_ignored: bool,
     pub run_tests: bool,
     pub bench_benchmarks: bool,
     pub logfile: Option<PathBuf>,
+    pub junit: Option<PathBuf>,
     pub nocapture: bool,
     pub color: ColorConfig,
     pub quiet: bool,
+    pub format: OutputFormat,
     pub test_threads: Option<usize>,
+    pub isolate: bool,
+    pub report_time: bool,
+    pub slowest: Option<usize>,
+    pub warn_time: Option<Duration>,
+    pub fail_time: Option<Duration>,
+    pub bench_warm_up: Option<Duration>,
+    pub bench_time: Option<Duration>,
+    pub baseline_dir: Option<PathBuf>,
+    pub save_baseline: Option<String>,
+    pub baseline: Option<String>,
 }
 
 impl TestOpts {
     #[cfg(test)]
     fn new() -> TestOpts {
         TestOpts {
-            filter: None,
+            filters: Vec::new(),
+            filter_exact: false,
+            skip: Vec::new(),
+            list: false,
+            shard: None,
+            shuffle_seed: None,
             run_ignored: false,
             run_tests: false,
             bench_benchmarks: false,
             logfile: None,
+            junit: None,
             nocapture: false,
             color: AutoColor,
             quiet: false,
+            format: PrettyFormat,
             test_threads: None,
+            isolate: false,
+            report_time: false,
+            slowest: None,
+            warn_time: None,
+            fail_time: None,
+            bench_warm_up: None,
+            bench_time: None,
+            baseline_dir: None,
+            save_baseline: None,
+            baseline: None,
         }
     }
 }
@@ -331,13 +403,45 @@ fn optgroups() -> Vec<getopts::OptGroup> {
       getopts::optflag("", "test", "Run tests and not benchmarks"),
       getopts::optflag("", "bench", "Run benchmarks instead of tests"),
       getopts::optflag("h", "help", "Display this message (longer with --help)"),
+      getopts::optflag("", "exact", "Only run tests whose name exactly matches a filter"),
+      getopts::optmulti("", "skip", "Skip tests whose names contain FILTER (this flag can \
+                                     be used multiple times)", "FILTER"),
+      getopts::optflag("", "list", "List all tests and benchmarks instead of running them"),
+      getopts::optopt("", "shard", "Only run the K-th of N roughly equal parts of the \
+                                    tests, K counting from 1", "K/N"),
+      getopts::optflag("", "shuffle", "Run tests in a random order"),
+      getopts::optopt("", "shuffle-seed", "Run tests in the random order given by SEED, \
+                                           as printed by a previous --shuffle run", "SEED"),
       getopts::optopt("", "logfile", "Write logs to the specified file instead \
                           of stdout", "PATH"),
+      getopts::optopt("", "junit", "Write a JUnit XML report of the test run to the \
+                                    specified file", "PATH"),
       getopts::optflag("", "nocapture", "don't capture stdout/stderr of each \
                                          task, allow printing directly"),
       getopts::optopt("", "test-threads", "Number of threads used for running tests \
                                            in parallel", "n_threads"),
+      getopts::optflag("", "isolate", "Run each test in its own process, so that a crash \
+                                       only fails the test which caused it"),
+      getopts::optflag("", "report-time", "Show the execution time of each test"),
+      getopts::optopt("", "slowest", "Print the N slowest tests at the end of the run", "N"),
+      getopts::optopt("", "warn-time", "Warn about tests running for longer than SECS \
+                                        seconds (default 60)", "SECS"),
+      getopts::optopt("", "fail-time", "Fail tests running for longer than SECS seconds \
+                                        instead of waiting for them", "SECS"),
+      getopts::optopt("", "bench-warm-up", "Run each benchmark for SECS seconds before \
+                                            measuring it (default 0)", "SECS"),
+      getopts::optopt("", "bench-time", "Measure each benchmark for at most SECS seconds \
+                                         (default 3)", "SECS"),
+      getopts::optopt("", "save-baseline", "Save the benchmark results as the baseline NAME",
+                      "NAME"),
+      getopts::optopt("", "baseline", "Compare the benchmark results with the baseline NAME",
+                      "NAME"),
+      getopts::optopt("", "baseline-dir", "Directory to save and load baselines in \
+                                           (default target/bench-baselines)", "PATH"),
       getopts::optflag("q", "quiet", "Display one character per test instead of one line"),
+      getopts::optopt("", "format", "Configure formatting of output:
+            pretty = human readable output (default);
+            json   = one JSON object per test event, one per line", "pretty|json"),
       getopts::optopt("", "color", "Configure coloring of output:
             auto   = colorize if stdout is a tty and tests are run on serially (default);
             always = always colorize output;
@@ -345,11 +449,26 @@ fn optgroups() -> Vec<getopts::OptGroup> {
 }
 
 fn usage(binary: &str) {
-    let message = format!("Usage: {} [OPTIONS] [FILTER]", binary);
+    let message = format!("Usage: {} [OPTIONS] [FILTER...]", binary);
     println!(r#"{usage}
 
-The FILTER string is tested against the name of all tests, and only those
-tests whose names contain the filter are run.
+The FILTER strings are tested against the name of all tests, and only those
+tests whose names contain one of the filters are run. With --exact a test
+name has to be equal to a filter instead. Tests matching a --skip filter are
+never run.
+
+With --shard K/N the filtered tests are split into N parts based on a hash of
+their names, and only the K-th part is run. The split does not depend on the
+machine or on which other tests exist, so N runners can each pick one shard.
+
+With --shuffle the tests are run in a random order, and the seed used is
+printed. Passing that seed to --shuffle-seed replays the same order.
+
+Benchmark results can be saved with --save-baseline NAME and later compared
+against with --baseline NAME. A change is reported as a regression or an
+improvement only if it is statistically significant, which is decided by a
+Mann-Whitney U test together with a bootstrapped confidence interval for the
+change of the median.
 
 By default, all tests are run in parallel. This can be altered with the
 --test-threads flag or the RUST_TEST_THREADS environment variable when running
@@ -359,6 +478,11 @@ All tests have their standard output and standard error captured by default.
 This can be overridden with the --nocapture flag or setting RUST_TEST_NOCAPTURE
 environment variable to a value other than "0". Logging is not captured by default.
 
+With the --isolate flag the test binary re-executes itself once per test, so
+that a segfault, abort or `process::exit` only fails the test that caused it.
+This also makes it possible to test crates compiled with `-C panic=abort`,
+and tests running past --fail-time are killed rather than left running.
+
 Test Attributes:
 
     #[test]        - Indicates a function is a test to be run. This function
@@ -389,10 +513,44 @@ pub fn parse_opts(args: &[String]) -> Option<OptRes> {
         return None;
     }
 
-    let filter = if !matches.free.is_empty() {
-        Some(matches.free[0].clone())
-    } else {
-        None
+    let filters = matches.free.clone();
+    let filter_exact = matches.opt_present("exact");
+    let skip = matches.opt_strs("skip");
+    let list = matches.opt_present("list");
+
+    let shard = match matches.opt_str("shard") {
+        Some(s) => {
+            let parsed = s.find('/').and_then(|i| {
+                match (s[..i].parse::<usize>(), s[i + 1..].parse::<usize>()) {
+                    (Ok(k), Ok(n)) if 1 <= k && k <= n => Some((k, n)),
+                    _ => None,
+                }
+            });
+            match parsed {
+                Some(shard) => Some(shard),
+                None => {
+                    return Some(Err(format!("argument for --shard must be of the form K/N \
+                                             with 1 <= K <= N (was {})",
+                                            s)))
+                }
+            }
+        }
+        None => None,
+    };
+
+    let shuffle_seed = match matches.opt_str("shuffle-seed") {
+        Some(s) => {
+            match s.parse::<u64>() {
+                Ok(seed) => Some(seed),
+                Err(e) => {
+                    return Some(Err(format!("argument for --shuffle-seed must be a number \
+                                             (error: {})",
+                                            e)))
+                }
+            }
+        }
+        None if matches.opt_present("shuffle") => Some(random_seed()),
+        None => None,
     };
 
     let run_ignored = matches.opt_present("ignored");
@@ -401,6 +559,8 @@ pub fn parse_opts(args: &[String]) -> Option<OptRes> {
     let logfile = matches.opt_str("logfile");
     let logfile = logfile.map(|s| PathBuf::from(&s));
 
+    let junit = matches.opt_str("junit").map(|s| PathBuf::from(&s));
+
     let bench_benchmarks = matches.opt_present("bench");
     let run_tests = !bench_benchmarks || matches.opt_present("test");
 
@@ -424,6 +584,42 @@ pub fn parse_opts(args: &[String]) -> Option<OptRes> {
             None,
     };
 
+    let isolate = matches.opt_present("isolate");
+    let report_time = matches.opt_present("report-time");
+
+    let slowest = match matches.opt_str("slowest") {
+        Some(n_str) =>
+            match n_str.parse::<usize>() {
+                Ok(n) => Some(n),
+                Err(e) =>
+                    return Some(Err(format!("argument for --slowest must be a number \
+                                             (error: {})", e)))
+            },
+        None =>
+            None,
+    };
+
+    let warn_time = match parse_secs(&matches, "warn-time") {
+        Ok(t) => t,
+        Err(e) => return Some(Err(e)),
+    };
+    let fail_time = match parse_secs(&matches, "fail-time") {
+        Ok(t) => t,
+        Err(e) => return Some(Err(e)),
+    };
+
+    let bench_warm_up = match parse_secs(&matches, "bench-warm-up") {
+        Ok(t) => t,
+        Err(e) => return Some(Err(e)),
+    };
+    let bench_time = match parse_secs(&matches, "bench-time") {
+        Ok(t) => t,
+        Err(e) => return Some(Err(e)),
+    };
+    let baseline_dir = matches.opt_str("baseline-dir").map(|s| PathBuf::from(&s));
+    let save_baseline = matches.opt_str("save-baseline");
+    let baseline = matches.opt_str("baseline");
+
     let color = match matches.opt_str("color").as_ref().map(|s| &**s) {
         Some("auto") | None => AutoColor,
         Some("always") => AlwaysColor,
@@ -436,27 +632,120 @@ pub fn parse_opts(args: &[String]) -> Option<OptRes> {
         }
     };
 
+    let format = match matches.opt_str("format").as_ref().map(|s| &**s) {
+        Some("pretty") | None => PrettyFormat,
+        Some("json") => JsonFormat,
+
+        Some(v) => {
+            return Some(Err(format!("argument for --format must be pretty or json (was {})",
+                                    v)))
+        }
+    };
+
     let test_opts = TestOpts {
-        filter: filter,
+        filters: filters,
+        filter_exact: filter_exact,
+        skip: skip,
+        list: list,
+        shard: shard,
+        shuffle_seed: shuffle_seed,
         run_ignored: run_ignored,
         run_tests: run_tests,
         bench_benchmarks: bench_benchmarks,
         logfile: logfile,
+        junit: junit,
         nocapture: nocapture,
         color: color,
         quiet: quiet,
+        format: format,
         test_threads: test_threads,
+        isolate: isolate,
+        report_time: report_time,
+        slowest: slowest,
+        warn_time: warn_time,
+        fail_time: fail_time,
+        bench_warm_up: bench_warm_up,
+        bench_time: bench_time,
+        baseline_dir: baseline_dir,
+        save_baseline: save_baseline,
+        baseline: baseline,
     };
 
     Some(Ok(test_opts))
 }
 
+fn parse_secs(matches: &getopts::Matches, name: &str) -> Result<Option<Duration>, String> {
+    match matches.opt_str(name) {
+        Some(s) => {
+            match s.parse::<u64>() {
+                Ok(secs) => Ok(Some(Duration::from_secs(secs))),
+                Err(e) => Err(format!("argument for --{} must be a number of seconds \
+                                       (error: {})", name, e)),
+            }
+        }
+        None => Ok(None),
+    }
+}
+
 #[derive(Clone, PartialEq)]
 pub struct BenchSamples {
     ns_iter_summ: stats::Summary,
+    ns_iter_samples: Vec<f64>,
     mb_s: usize,
 }
 
+// Benchmark results saved with `--save-baseline`, keyed by benchmark name
+type Baseline = BTreeMap<String, Vec<f64>>;
+
+fn baseline_path(opts: &TestOpts, name: &str) -> PathBuf {
+    let dir = match opts.baseline_dir {
+        Some(ref dir) => dir.clone(),
+        None => PathBuf::from("target/bench-baselines"),
+    };
+    dir.join(format!("{}.txt", name))
+}
+
+// Baselines are stored with one benchmark per line, as its name and the
+// per-iteration times of all its samples separated by tabs.
+fn load_baseline(path: &Path) -> io::Result<Baseline> {
+    let mut contents = String::new();
+    File::open(path)?.read_to_string(&mut contents)?;
+
+    let mut baseline = BTreeMap::new();
+    for line in contents.lines() {
+        let mut parts = line.split('\t');
+        let name = match parts.next() {
+            Some(name) if !name.is_empty() => name,
+            _ => continue,
+        };
+        let samples = parts.map(|s| s.parse::<f64>())
+                           .collect::<Result<Vec<_>, _>>()
+                           .map_err(|e| {
+                               io::Error::new(io::ErrorKind::InvalidData,
+                                              format!("malformed baseline {}: {}",
+                                                      path.display(),
+                                                      e))
+                           })?;
+        baseline.insert(name.to_owned(), samples);
+    }
+    Ok(baseline)
+}
+
+fn save_baseline(path: &Path, baseline: &Baseline) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut out = File::create(path)?;
+    for (name, samples) in baseline {
+        write!(out, "{}", name)?;
+        for sample in samples {
+            write!(out, "\t{}", sample)?;
+        }
+        writeln!(out, "")?;
+    }
+    Ok(())
+}
+
 #[derive(Clone, PartialEq)]
 pub enum TestResult {
     TrOk,
@@ -475,9 +764,19 @@ enum OutputLocation<T> {
 
 struct ConsoleTestState<T> {
     log_out: Option<File>,
+    junit_out: Option<File>,
+    junit_results: Vec<(TestDesc, TestResult, Duration, Vec<u8>)>,
     out: OutputLocation<T>,
     use_color: bool,
     quiet: bool,
+    format: OutputFormat,
+    shuffle_seed: Option<u64>,
+    report_time: bool,
+    slowest: Option<usize>,
+    warn_time: Duration,
+    exec_times: Vec<(TestName, Duration)>,
+    baseline: Option<(String, Baseline)>,
+    save_baseline: Option<(PathBuf, Baseline)>,
     total: usize,
     passed: usize,
     failed: usize,
@@ -494,6 +793,28 @@ impl<T: Write> ConsoleTestState<T> {
             Some(ref path) => Some(File::create(path)?),
             None => None,
         };
+        let baseline = match opts.baseline {
+            Some(ref name) => Some((name.clone(), load_baseline(&baseline_path(opts, name))?)),
+            None => None,
+        };
+        // A run of only some of the benchmarks keeps the saved results of
+        // the others.
+        let save_baseline = match opts.save_baseline {
+            Some(ref name) => {
+                let path = baseline_path(opts, name);
+                let baseline = match load_baseline(&path) {
+                    Ok(baseline) => baseline,
+                    Err(ref e) if e.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+                    Err(e) => return Err(e),
+                };
+                Some((path, baseline))
+            }
+            None => None,
+        };
+        let junit_out = match opts.junit {
+            Some(ref path) => Some(File::create(path)?),
+            None => None,
+        };
         let out = match term::stdout() {
             None => Raw(io::stdout()),
             Some(t) => Pretty(t),
@@ -502,8 +823,18 @@ impl<T: Write> ConsoleTestState<T> {
         Ok(ConsoleTestState {
             out: out,
             log_out: log_out,
+            junit_out: junit_out,
+            junit_results: Vec::new(),
             use_color: use_color(opts),
             quiet: opts.quiet,
+            format: opts.format,
+            shuffle_seed: opts.shuffle_seed,
+            report_time: opts.report_time,
+            slowest: opts.slowest,
+            warn_time: opts.warn_time.unwrap_or(Duration::from_secs(TEST_WARN_TIMEOUT_S)),
+            exec_times: Vec::new(),
+            baseline: baseline,
+            save_baseline: save_baseline,
             total: 0,
             passed: 0,
             failed: 0,
@@ -515,16 +846,16 @@ impl<T: Write> ConsoleTestState<T> {
         })
     }
 
-    pub fn write_ok(&mut self) -> io::Result<()> {
-        self.write_short_result("ok", ".", term::color::GREEN)
+    pub fn write_ok(&mut self, exec_time: Option<Duration>) -> io::Result<()> {
+        self.write_short_result("ok", ".", term::color::GREEN, exec_time)
     }
 
-    pub fn write_failed(&mut self) -> io::Result<()> {
-        self.write_short_result("FAILED", "F", term::color::RED)
+    pub fn write_failed(&mut self, exec_time: Option<Duration>) -> io::Result<()> {
+        self.write_short_result("FAILED", "F", term::color::RED, exec_time)
     }
 
     pub fn write_ignored(&mut self) -> io::Result<()> {
-        self.write_short_result("ignored", "i", term::color::YELLOW)
+        self.write_short_result("ignored", "i", term::color::YELLOW, None)
     }
 
     pub fn write_metric(&mut self) -> io::Result<()> {
@@ -535,13 +866,20 @@ impl<T: Write> ConsoleTestState<T> {
         self.write_pretty("bench", term::color::CYAN)
     }
 
-    pub fn write_short_result(&mut self, verbose: &str, quiet: &str, color: term::color::Color)
+    pub fn write_short_result(&mut self,
+                              verbose: &str,
+                              quiet: &str,
+                              color: term::color::Color,
+                              exec_time: Option<Duration>)
                               -> io::Result<()> {
         if self.quiet {
             self.write_pretty(quiet, color)
         } else {
             self.write_pretty(verbose, color)?;
-            self.write_plain("\n")
+            match exec_time {
+                Some(t) => self.write_plain(&format!(" <{:.3}s>\n", duration_as_secs_f64(t))),
+                None => self.write_plain("\n"),
+            }
         }
     }
 
@@ -577,18 +915,46 @@ impl<T: Write> ConsoleTestState<T> {
         }
     }
 
+    fn write_json(&mut self, fields: &[(&str, JsonValue)]) -> io::Result<()> {
+        let fields = fields.iter()
+                           .map(|&(k, ref v)| format!("{}: {}", JsonValue::Str(k), v))
+                           .collect::<Vec<_>>();
+        self.write_plain(&format!("{{ {} }}\n", fields.join(", ")))
+    }
+
     pub fn write_run_start(&mut self, len: usize) -> io::Result<()> {
         self.total = len;
+        if self.format == JsonFormat {
+            // Seeds are written as strings as they don't fit into a double
+            let seed = self.shuffle_seed.map(|seed| seed.to_string());
+            let mut fields = vec![("type", JsonValue::Str("suite")),
+                                  ("event", JsonValue::Str("started")),
+                                  ("test_count", JsonValue::Num(len as f64))];
+            if let Some(ref seed) = seed {
+                fields.push(("shuffle_seed", JsonValue::Str(seed)));
+            }
+            return self.write_json(&fields);
+        }
         let noun = if len != 1 {
             "tests"
         } else {
             "test"
         };
-        self.write_plain(&format!("\nrunning {} {}\n", len, noun))
+        let s = match self.shuffle_seed {
+            Some(seed) => {
+                format!("\nrunning {} {} (shuffled, --shuffle-seed {})\n", len, noun, seed)
+            }
+            None => format!("\nrunning {} {}\n", len, noun),
+        };
+        self.write_plain(&s)
     }
 
     pub fn write_test_start(&mut self, test: &TestDesc, align: NamePadding) -> io::Result<()> {
-        if self.quiet && align != PadOnRight {
+        if self.format == JsonFormat {
+            self.write_json(&[("type", JsonValue::Str("test")),
+                              ("event", JsonValue::Str("started")),
+                              ("name", JsonValue::Str(test.name.as_slice()))])
+        } else if self.quiet && align != PadOnRight {
             Ok(())
         } else {
             let name = test.padded_name(self.max_name_len, align);
@@ -596,10 +962,19 @@ impl<T: Write> ConsoleTestState<T> {
         }
     }
 
-    pub fn write_result(&mut self, result: &TestResult) -> io::Result<()> {
+    pub fn write_result(&mut self,
+                        test: &TestDesc,
+                        result: &TestResult,
+                        exec_time: Duration,
+                        stdout: &[u8])
+                        -> io::Result<()> {
+        if self.format == JsonFormat {
+            return self.write_json_result(test, result, exec_time, stdout);
+        }
+        let exec_time = if self.report_time { Some(exec_time) } else { None };
         match *result {
-            TrOk => self.write_ok(),
-            TrFailed => self.write_failed(),
+            TrOk => self.write_ok(exec_time),
+            TrFailed => self.write_failed(exec_time),
             TrIgnored => self.write_ignored(),
             TrMetrics(ref mm) => {
                 self.write_metric()?;
@@ -607,15 +982,87 @@ impl<T: Write> ConsoleTestState<T> {
             }
             TrBench(ref bs) => {
                 self.write_bench()?;
-                self.write_plain(&format!(": {}\n", fmt_bench_samples(bs)))
+                self.write_plain(&format!(": {}\n", fmt_bench_samples(bs)))?;
+                match self.compare_to_baseline(test, bs) {
+                    Some((name, cmp)) => {
+                        let s = format!("    vs. baseline `{}`: {}\n", name, fmt_comparison(&cmp));
+                        self.write_plain(&s)
+                    }
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+
+    fn compare_to_baseline(&self, test: &TestDesc, bs: &BenchSamples)
+                           -> Option<(String, stats::Comparison)> {
+        let &(ref name, ref baseline) = match self.baseline {
+            Some(ref baseline) => baseline,
+            None => return None,
+        };
+        match baseline.get(test.name.as_slice()) {
+            Some(old) if !old.is_empty() && !bs.ns_iter_samples.is_empty() => {
+                Some((name.clone(), stats::Comparison::new(old, &bs.ns_iter_samples)))
+            }
+            _ => None,
+        }
+    }
+
+    fn write_json_result(&mut self,
+                         test: &TestDesc,
+                         result: &TestResult,
+                         exec_time: Duration,
+                         stdout: &[u8])
+                         -> io::Result<()> {
+        let stdout = String::from_utf8_lossy(stdout);
+        let exec_time = duration_as_secs_f64(exec_time);
+        let mut fields = vec![("type", JsonValue::Str("test")),
+                              ("name", JsonValue::Str(test.name.as_slice()))];
+        match *result {
+            TrOk => fields.push(("event", JsonValue::Str("ok"))),
+            TrFailed => {
+                fields.push(("event", JsonValue::Str("failed")));
+                fields.push(("stdout", JsonValue::Str(&stdout)));
             }
+            TrIgnored => fields.push(("event", JsonValue::Str("ignored"))),
+            TrMetrics(ref mm) => {
+                fields[0].1 = JsonValue::Str("metric");
+                fields.push(("event", JsonValue::Str("ok")));
+                let metrics = mm.0.iter().map(|(k, v)| (&k[..], JsonValue::Num(v.value)));
+                fields.push(("metrics", JsonValue::Obj(metrics.collect())));
+            }
+            TrBench(ref bs) => {
+                fields[0].1 = JsonValue::Str("bench");
+                fields.push(("event", JsonValue::Str("ok")));
+                fields.push(("median", JsonValue::Num(bs.ns_iter_summ.median)));
+                fields.push(("deviation",
+                             JsonValue::Num(bs.ns_iter_summ.max - bs.ns_iter_summ.min)));
+                fields.push(("mb_s", JsonValue::Num(bs.mb_s as f64)));
+                if let Some((_, cmp)) = self.compare_to_baseline(test, bs) {
+                    fields.push(("change", JsonValue::Num(cmp.change)));
+                    fields.push(("change_lower", JsonValue::Num(cmp.change_ci.0)));
+                    fields.push(("change_upper", JsonValue::Num(cmp.change_ci.1)));
+                    fields.push(("p_value", JsonValue::Num(cmp.p_value)));
+                    fields.push(("verdict", JsonValue::Str(cmp.verdict())));
+                }
+            }
+        }
+        if *result != TrIgnored {
+            fields.push(("exec_time", JsonValue::Num(exec_time)));
         }
+        self.write_json(&fields)
     }
 
     pub fn write_timeout(&mut self, desc: &TestDesc) -> io::Result<()> {
+        if self.format == JsonFormat {
+            return self.write_json(&[("type", JsonValue::Str("test")),
+                                     ("event", JsonValue::Str("timeout")),
+                                     ("name", JsonValue::Str(desc.name.as_slice()))]);
+        }
+        let secs = self.warn_time.as_secs();
         self.write_plain(&format!("test {} has been running for over {} seconds\n",
                                   desc.name,
-                                  TEST_WARN_TIMEOUT_S))
+                                  secs))
     }
 
     pub fn write_log(&mut self, test: &TestDesc, result: &TestResult) -> io::Result<()> {
@@ -636,6 +1083,83 @@ impl<T: Write> ConsoleTestState<T> {
         }
     }
 
+    pub fn write_junit(&mut self) -> io::Result<()> {
+        let mut o = match self.junit_out {
+            None => return Ok(()),
+            Some(ref o) => o,
+        };
+        let suite = env::args()
+                        .next()
+                        .and_then(|arg0| {
+                            Path::new(&arg0).file_stem().map(|s| s.to_string_lossy().into_owned())
+                        })
+                        .unwrap_or_else(|| "test".to_owned());
+        let total_time = self.junit_results
+                             .iter()
+                             .fold(Duration::new(0, 0), |acc, &(_, _, time, _)| acc + time);
+
+        writeln!(o, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(o, "<testsuites>")?;
+        writeln!(o,
+                 "  <testsuite name=\"{}\" tests=\"{}\" errors=\"0\" failures=\"{}\" \
+                  skipped=\"{}\" time=\"{}\">",
+                 xml_escape(&suite),
+                 self.junit_results.len(),
+                 self.failed,
+                 self.ignored,
+                 duration_as_secs_f64(total_time))?;
+        for &(ref desc, ref result, exec_time, ref stdout) in &self.junit_results {
+            // Split `a::b::c` into the class `a::b` and the test `c`, which is
+            // how JUnit consumers expect to group test cases.
+            let name = desc.name.as_slice();
+            let (class, name) = match name.rfind("::") {
+                Some(i) => (&name[..i], &name[i + 2..]),
+                None => (&suite[..], name),
+            };
+            write!(o,
+                   r#"    <testcase classname="{}" name="{}" time="{}""#,
+                   xml_escape(class),
+                   xml_escape(name),
+                   duration_as_secs_f64(exec_time))?;
+            let stdout = String::from_utf8_lossy(stdout);
+            match *result {
+                TrOk if stdout.is_empty() => writeln!(o, "/>")?,
+                TrOk => {
+                    writeln!(o, ">")?;
+                    writeln!(o, "      <system-out>{}</system-out>", xml_escape(&stdout))?;
+                    writeln!(o, "    </testcase>")?;
+                }
+                TrFailed => {
+                    writeln!(o, ">")?;
+                    writeln!(o, r#"      <failure message="test failed"/>"#)?;
+                    writeln!(o, "      <system-out>{}</system-out>", xml_escape(&stdout))?;
+                    writeln!(o, "    </testcase>")?;
+                }
+                TrIgnored => {
+                    writeln!(o, ">")?;
+                    writeln!(o, "      <skipped/>")?;
+                    writeln!(o, "    </testcase>")?;
+                }
+                TrMetrics(ref mm) => {
+                    writeln!(o, ">")?;
+                    writeln!(o,
+                             "      <system-out>{}</system-out>",
+                             xml_escape(&mm.fmt_metrics()))?;
+                    writeln!(o, "    </testcase>")?;
+                }
+                TrBench(ref bs) => {
+                    writeln!(o, ">")?;
+                    writeln!(o,
+                             "      <system-out>{}</system-out>",
+                             xml_escape(&fmt_bench_samples(bs)))?;
+                    writeln!(o, "    </testcase>")?;
+                }
+            }
+        }
+        writeln!(o, "  </testsuite>")?;
+        writeln!(o, "</testsuites>")
+    }
+
     pub fn write_failures(&mut self) -> io::Result<()> {
         self.write_plain("\nfailures:\n")?;
         let mut failures = Vec::new();
@@ -662,13 +1186,50 @@ impl<T: Write> ConsoleTestState<T> {
         Ok(())
     }
 
+    pub fn write_slowest(&mut self, n: usize) -> io::Result<()> {
+        let mut times = self.exec_times.clone();
+        times.sort_by(|a, b| b.1.cmp(&a.1));
+        times.truncate(n);
+        if times.is_empty() {
+            return Ok(());
+        }
+        self.write_plain(&format!("\nslowest {} tests:\n", times.len()))?;
+        for (name, time) in times {
+            self.write_plain(&format!("    {:>9.3}s {}\n", duration_as_secs_f64(time), name))?;
+        }
+        Ok(())
+    }
+
     pub fn write_run_finish(&mut self) -> io::Result<bool> {
         assert!(self.passed + self.failed + self.ignored + self.measured == self.total);
 
+        self.write_junit()?;
+        // A run without benchmarks, like one missing `--bench`, mustn't
+        // replace the baseline.
+        if let Some((ref path, ref baseline)) = self.save_baseline {
+            if !baseline.is_empty() {
+                save_baseline(path, baseline)?;
+            }
+        }
+
         let success = self.failed == 0;
+        if self.format == JsonFormat {
+            let (passed, failed) = (self.passed, self.failed);
+            let (ignored, measured) = (self.ignored, self.measured);
+            self.write_json(&[("type", JsonValue::Str("suite")),
+                              ("event", JsonValue::Str(if success { "ok" } else { "failed" })),
+                              ("passed", JsonValue::Num(passed as f64)),
+                              ("failed", JsonValue::Num(failed as f64)),
+                              ("ignored", JsonValue::Num(ignored as f64)),
+                              ("measured", JsonValue::Num(measured as f64))])?;
+            return Ok(success);
+        }
         if !success {
             self.write_failures()?;
         }
+        if let Some(n) = self.slowest {
+            self.write_slowest(n)?;
+        }
 
         self.write_plain("\ntest result: ")?;
         if success {
@@ -711,6 +1272,80 @@ fn fmt_thousands_sep(mut n: usize, sep: char) -> String {
     output
 }
 
+// A value in one of the single-line JSON objects written by `--format json`
+enum JsonValue<'a> {
+    Str(&'a str),
+    Num(f64),
+    Obj(Vec<(&'a str, JsonValue<'a>)>),
+}
+
+impl<'a> fmt::Display for JsonValue<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            // JSON has no NaN or infinities
+            JsonValue::Num(n) if !n.is_finite() => f.write_str("null"),
+            JsonValue::Num(n) => write!(f, "{}", n),
+            JsonValue::Obj(ref fields) if fields.is_empty() => f.write_str("{}"),
+            JsonValue::Obj(ref fields) => {
+                f.write_str("{ ")?;
+                for (i, &(k, ref v)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}: {}", JsonValue::Str(k), v)?;
+                }
+                f.write_str(" }")
+            }
+            JsonValue::Str(s) => {
+                f.write_str("\"")?;
+                for c in s.chars() {
+                    match c {
+                        '"' => f.write_str("\\\"")?,
+                        '\\' => f.write_str("\\\\")?,
+                        '\n' => f.write_str("\\n")?,
+                        '\r' => f.write_str("\\r")?,
+                        '\t' => f.write_str("\\t")?,
+                        c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+                        c => write!(f, "{}", c)?,
+                    }
+                }
+                f.write_str("\"")
+            }
+        }
+    }
+}
+
+fn xml_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Other control characters are not allowed in XML 1.0 at all
+            '\n' | '\r' | '\t' => escaped.push(c),
+            c if (c as u32) < 0x20 => escaped.push('\u{fffd}'),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn duration_as_secs_f64(d: Duration) -> f64 {
+    d.as_secs() as f64 + d.subsec_nanos() as f64 / 1_000_000_000.0
+}
+
+fn fmt_comparison(cmp: &stats::Comparison) -> String {
+    format!("{:+.2}% [{:+.2}% .. {:+.2}%], p = {:.3}: {}",
+            cmp.change * 100.0,
+            cmp.change_ci.0 * 100.0,
+            cmp.change_ci.1 * 100.0,
+            cmp.p_value,
+            cmp.verdict())
+}
+
 pub fn fmt_bench_samples(bs: &BenchSamples) -> String {
     use std::fmt::Write;
     let mut output = String::new();
@@ -728,17 +1363,81 @@ pub fn fmt_bench_samples(bs: &BenchSamples) -> String {
     output
 }
 
+// Prints the tests and benchmarks which would be run, without running them
+pub fn list_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Result<()> {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+
+    let mut ntests = 0;
+    let mut nbenches = 0;
+    let mut nmetrics = 0;
+
+    let mut filtered = filter_tests(opts, tests);
+    if let Some((k, n)) = opts.shard {
+        filtered = shard_tests(filtered, k, n);
+    }
+
+    for test in filtered {
+        let fntype = match test.testfn {
+            StaticTestFn(..) | DynTestFn(..) | StaticTestCasesFn(..) => {
+                ntests += 1;
+                "test"
+            }
+            StaticBenchFn(..) | DynBenchFn(..) => {
+                nbenches += 1;
+                "benchmark"
+            }
+            StaticMetricFn(..) | DynMetricFn(..) => {
+                nmetrics += 1;
+                "metric"
+            }
+        };
+        writeln!(out, "{}: {}", test.desc.name, fntype)?;
+    }
+
+    if !opts.quiet {
+        fn plural(count: u32, s: &str) -> String {
+            match count {
+                1 => format!("{} {}", 1, s),
+                n => format!("{} {}s", n, s),
+            }
+        }
+
+        if ntests != 0 || nbenches != 0 || nmetrics != 0 {
+            writeln!(out, "")?;
+        }
+        writeln!(out,
+                 "{}, {}, {}",
+                 plural(ntests, "test"),
+                 plural(nbenches, "benchmark"),
+                 plural(nmetrics, "metric"))?;
+    }
+    Ok(())
+}
+
 // A simple console test runner
 pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Result<bool> {
+    if opts.list {
+        list_tests_console(opts, tests)?;
+        return Ok(true);
+    }
 
     fn callback<T: Write>(event: &TestEvent, st: &mut ConsoleTestState<T>) -> io::Result<()> {
         match (*event).clone() {
             TeFiltered(ref filtered_tests) => st.write_run_start(filtered_tests.len()),
             TeWait(ref test, padding) => st.write_test_start(test, padding),
             TeTimeout(ref test) => st.write_timeout(test),
-            TeResult(test, result, stdout) => {
+            TeResult(test, result, stdout, exec_time) => {
                 st.write_log(&test, &result)?;
-                st.write_result(&result)?;
+                match result {
+                    TrOk | TrFailed => st.exec_times.push((test.name.clone(), exec_time)),
+                    _ => {}
+                }
+                if st.junit_out.is_some() {
+                    st.junit_results
+                      .push((test.clone(), result.clone(), exec_time, stdout.clone()));
+                }
+                st.write_result(&test, &result, exec_time, &stdout)?;
                 match result {
                     TrOk => st.passed += 1,
                     TrIgnored => st.ignored += 1,
@@ -752,6 +1451,9 @@ pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Resu
                         st.measured += 1
                     }
                     TrBench(bs) => {
+                        if let Some((_, ref mut baseline)) = st.save_baseline {
+                            baseline.insert(test.name.to_string(), bs.ns_iter_samples.clone());
+                        }
                         st.metrics.insert_metric(test.name.as_slice(),
                                                  bs.ns_iter_summ.median,
                                                  bs.ns_iter_summ.max - bs.ns_iter_summ.min);
@@ -798,9 +1500,19 @@ fn should_sort_failures_before_printing_them() {
 
     let mut st = ConsoleTestState {
         log_out: None,
+        junit_out: None,
+        junit_results: Vec::new(),
         out: Raw(Vec::new()),
         use_color: false,
         quiet: false,
+        format: PrettyFormat,
+        shuffle_seed: None,
+        report_time: false,
+        slowest: None,
+        warn_time: Duration::from_secs(TEST_WARN_TIMEOUT_S),
+        exec_times: Vec::new(),
+        baseline: None,
+        save_baseline: None,
         total: 0,
         passed: 0,
         failed: 0,
@@ -822,6 +1534,70 @@ fn should_sort_failures_before_printing_them() {
     assert!(apos < bpos);
 }
 
+#[test]
+fn json_results_are_escaped_one_per_line() {
+    let test = TestDesc {
+        name: StaticTestName("a::\"b\""),
+        ignore: false,
+        should_panic: ShouldPanic::No,
+    };
+
+    let mut st = ConsoleTestState {
+        log_out: None,
+        junit_out: None,
+        junit_results: Vec::new(),
+        out: Raw(Vec::new()),
+        use_color: false,
+        quiet: false,
+        format: JsonFormat,
+        shuffle_seed: None,
+        report_time: false,
+        slowest: None,
+        warn_time: Duration::from_secs(TEST_WARN_TIMEOUT_S),
+        exec_times: Vec::new(),
+        baseline: None,
+        save_baseline: None,
+        total: 0,
+        passed: 0,
+        failed: 0,
+        ignored: 0,
+        measured: 0,
+        max_name_len: 10,
+        metrics: MetricMap::new(),
+        failures: Vec::new(),
+    };
+
+    st.write_result(&test, &TrFailed, Duration::from_millis(1500), b"line\n").unwrap();
+    let s = match st.out {
+        Raw(ref m) => String::from_utf8_lossy(&m[..]).into_owned(),
+        Pretty(_) => unreachable!(),
+    };
+
+    assert_eq!(s,
+               "{ \"type\": \"test\", \"name\": \"a::\\\"b\\\"\", \"event\": \"failed\", \
+                \"stdout\": \"line\\n\", \"exec_time\": 1.5 }\n");
+
+    let mut mm = MetricMap::new();
+    mm.insert_metric("name", 1.0, 0.0);
+    mm.insert_metric("type", ::std::f64::NAN, 0.0);
+    st.out = Raw(Vec::new());
+    st.write_result(&test, &TrMetrics(mm), Duration::from_millis(1500), b"").unwrap();
+    let s = match st.out {
+        Raw(ref m) => String::from_utf8_lossy(&m[..]),
+        Pretty(_) => unreachable!(),
+    };
+
+    assert_eq!(s,
+               "{ \"type\": \"metric\", \"name\": \"a::\\\"b\\\"\", \"event\": \"ok\", \
+                \"metrics\": { \"name\": 1, \"type\": null }, \"exec_time\": 1.5 }\n");
+}
+
+#[test]
+fn junit_text_is_xml_escaped() {
+    assert_eq!(xml_escape("<a href=\"x\">'&'</a>\n\u{1b}"),
+               "&lt;a href=&quot;x&quot;&gt;&apos;&amp;&apos;&lt;/a&gt;\n\u{fffd}");
+}
+
 fn use_color(opts: &TestOpts) -> bool {
     match opts.color {
         AutoColor => !opts.nocapture && stdout_isatty(),
@@ -856,7 +1632,7 @@ fn stdout_isatty() -> bool {
 enum TestEvent {
     TeFiltered(Vec<TestDesc>),
     TeWait(TestDesc, NamePadding),
-    TeResult(TestDesc, TestResult, Vec<u8>),
+    TeResult(TestDesc, TestResult, Vec<u8>, Duration),
     TeTimeout(TestDesc),
 }
 
@@ -867,9 +1643,14 @@ fn run_tests<F>(opts: &TestOpts, tests: Vec<TestDescAndFn>, mut callback: F) ->
     where F: FnMut(TestEvent) -> io::Result<()>
 {
     use std::collections::HashMap;
-    use std::sync::mpsc::RecvTimeoutError;
 
     let mut filtered_tests = filter_tests(opts, tests);
+    if let Some((k, n)) = opts.shard {
+        filtered_tests = shard_tests(filtered_tests, k, n);
+    }
+    if let Some(seed) = opts.shuffle_seed {
+        shuffle_tests(&mut filtered_tests, seed);
+    }
     if !opts.bench_benchmarks {
         filtered_tests = convert_benchmarks_to_tests(filtered_tests);
     }
@@ -899,7 +1680,12 @@ fn run_tests<F>(opts: &TestOpts, tests: Vec<TestDescAndFn>, mut callback: F) ->
 
     let (tx, rx) = channel::<MonitorMsg>();
 
+    let warn_time = opts.warn_time.unwrap_or(Duration::from_secs(TEST_WARN_TIMEOUT_S));
+
+    // Tests which have not yet been warned about, with the time they get a warning
     let mut running_tests: HashMap<TestDesc, Instant> = HashMap::new();
+    // All tests which are still running, with the time they were started
+    let mut start_times: HashMap<TestDesc, Instant> = HashMap::new();
 
     fn get_timed_out_tests(running_tests: &mut HashMap<TestDesc, Instant>) -> Vec<TestDesc> {
         let now = Instant::now();
@@ -912,16 +1698,43 @@ fn run_tests<F>(opts: &TestOpts, tests: Vec<TestDescAndFn>, mut callback: F) ->
         timed_out
     };
 
-    fn calc_timeout(running_tests: &HashMap<TestDesc, Instant>) -> Option<Duration> {
-        running_tests.values().min().map(|next_timeout| {
+    fn get_failed_tests(start_times: &HashMap<TestDesc, Instant>,
+                        fail_time: Option<Duration>)
+                        -> Vec<TestDesc> {
+        let fail_time = match fail_time {
+            Some(t) => t,
+            None => return Vec::new(),
+        };
+        let now = Instant::now();
+        start_times.iter()
+            .filter(|&(_, start)| now >= *start + fail_time)
+            .map(|(desc, _)| desc.clone())
+            .collect()
+    };
+
+    fn calc_timeout(running_tests: &HashMap<TestDesc, Instant>,
+                    start_times: &HashMap<TestDesc, Instant>,
+                    fail_time: Option<Duration>)
+                    -> Option<Duration> {
+        let next_warning = running_tests.values().min().cloned();
+        let next_failure = fail_time.and_then(|t| start_times.values().min().map(|s| *s + t));
+        let next_timeout = match (next_warning, next_failure) {
+            (Some(a), Some(b)) => Some(cmp::min(a, b)),
+            (a, b) => a.or(b),
+        };
+        next_timeout.map(|next_timeout| {
             let now = Instant::now();
-            if *next_timeout >= now {
-                *next_timeout - now
+            if next_timeout >= now {
+                next_timeout - now
             } else {
                 Duration::new(0, 0)
             }})
     };
 
+    // Tests run in their own process are killed when they run past
+    // `--fail-time`, and report that as their result themselves.
+    let fail_time = if opts.isolate { None } else { opts.fail_time };
+
     while pending > 0 || !remaining.is_empty() {
         while pending < concurrency && !remaining.is_empty() {
             let test = remaining.pop().unwrap();
@@ -931,46 +1744,77 @@ fn run_tests<F>(opts: &TestOpts, tests: Vec<TestDescAndFn>, mut callback: F) ->
                 // that hang forever.
                 callback(TeWait(test.desc.clone(), test.testfn.padding()))?;
             }
-            let timeout = Instant::now() + Duration::from_secs(TEST_WARN_TIMEOUT_S);
+            let now = Instant::now();
+            let timeout = now + warn_time;
             running_tests.insert(test.desc.clone(), timeout);
+            start_times.insert(test.desc.clone(), now);
             run_test(opts, !opts.run_tests, test, tx.clone());
             pending += 1;
         }
 
         let mut res;
+        let mut failed;
         loop {
-            if let Some(timeout) = calc_timeout(&running_tests) {
+            if let Some(timeout) = calc_timeout(&running_tests, &start_times, fail_time) {
                 res = rx.recv_timeout(timeout);
                 for test in get_timed_out_tests(&mut running_tests) {
                     callback(TeTimeout(test))?;
                 }
-                if res != Err(RecvTimeoutError::Timeout) {
+                failed = get_failed_tests(&start_times, fail_time);
+                if res != Err(RecvTimeoutError::Timeout) || !failed.is_empty() {
                     break;
                 }
             } else {
                 res = rx.recv().map_err(|_| RecvTimeoutError::Disconnected);
+                failed = Vec::new();
                 break;
             }
         }
 
-        let (desc, result, stdout) = res.unwrap();
+        // Tests which ran past `--fail-time` are reported as failures right
+        // away. Their threads can't be killed, so they are left running and
+        // whatever result they send later is dropped.
+        for desc in failed {
+            running_tests.remove(&desc);
+            let exec_time = start_times.remove(&desc).unwrap().elapsed();
+            let msg = format!("test did not finish within the time limit of {} seconds\n",
+                              fail_time.unwrap().as_secs());
+            if concurrency != 1 {
+                callback(TeWait(desc.clone(), PadNone))?;
+            }
+            callback(TeResult(desc, TrFailed, msg.into_bytes(), exec_time))?;
+            pending -= 1;
+        }
+
+        let (desc, result, stdout) = match res {
+            Err(RecvTimeoutError::Timeout) => continue,
+            res => res.unwrap(),
+        };
         running_tests.remove(&desc);
+        let exec_time = match start_times.remove(&desc) {
+            Some(start) => start.elapsed(),
+            None => continue,
+        };
 
         if concurrency != 1 {
             callback(TeWait(desc.clone(), PadNone))?;
         }
-        callback(TeResult(desc, result, stdout))?;
+        callback(TeResult(desc, result, stdout, exec_time))?;
         pending -= 1;
     }
 
     if opts.bench_benchmarks {
         // All benchmarks run at the end, in serial.
         // (this includes metric fns)
+        // They get a channel of their own, so the late result of a test
+        // abandoned by `--fail-time` can't be taken for the result of a bench.
+        let (tx, rx) = channel::<MonitorMsg>();
         for b in filtered_benchs_and_metrics {
             callback(TeWait(b.desc.clone(), b.testfn.padding()))?;
+            let start = Instant::now();
             run_test(opts, false, b, tx.clone());
             let (test, result, stdout) = rx.recv().unwrap();
-            callback(TeResult(test, result, stdout))?;
+            callback(TeResult(test, result, stdout, start.elapsed()))?;
         }
     }
     Ok(())
@@ -1083,18 +1927,43 @@ fn get_concurrency() -> usize {
 }
 
 pub fn filter_tests(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> Vec<TestDescAndFn> {
-    let mut filtered = tests;
-
-    // Remove tests that don't match the test filter
-    filtered = match opts.filter {
-        None => filtered,
-        Some(ref filter) => {
-            filtered.into_iter()
-                    .filter(|test| test.desc.name.as_slice().contains(&filter[..]))
-                    .collect()
+    // Test case generators whose cases would all be filtered out aren't run.
+    // This is known in advance for some filters, as the names of all cases
+    // start with the name of their generator followed by `::`.
+    let tests = tests.into_iter().filter(|test| {
+        let prefix = match test.testfn {
+            StaticTestCasesFn(..) => format!("{}::", test.desc.name),
+            _ => return true,
+        };
+        if opts.run_ignored && !test.desc.ignore {
+            return false;
+        }
+        if opts.filter_exact && !opts.filters.is_empty() &&
+           !opts.filters.iter().any(|filter| filter.starts_with(&prefix)) {
+            return false;
+        }
+        opts.filter_exact ||
+            !opts.skip.iter().any(|sf| test.desc.name.as_slice().contains(&sf[..]))
+    }).collect();
+    let mut filtered = expand_test_cases(tests);
+
+    let matches_filter = |test: &TestDescAndFn, filter: &str| {
+        let name = test.desc.name.as_slice();
+        if opts.filter_exact {
+            name == filter
+        } else {
+            name.contains(filter)
         }
     };
 
+    // Remove tests that don't match any of the test filters
+    if !opts.filters.is_empty() {
+        filtered.retain(|test| opts.filters.iter().any(|filter| matches_filter(test, &filter[..])));
+    }
+
+    // Remove tests that match any of the skip filters
+    filtered.retain(|test| !opts.skip.iter().any(|sf| matches_filter(test, &sf[..])));
+
     // Maybe pull out the ignored test and unignore them
     filtered = if !opts.run_ignored {
         filtered
@@ -1119,6 +1988,108 @@ pub fn filter_tests(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> Vec<TestDescA
     filtered
 }
 
+/// Replaces every `#[test_cases]` generator with the test cases it returns.
+///
+/// Each case is named after its generator followed by `::` and the name of
+/// the case, and inherits `#[ignore]` and `#[should_panic]` from the
+/// generator. If the generator itself panics, a single failing test with the
+/// name of the generator is reported instead.
+pub fn expand_test_cases(tests: Vec<TestDescAndFn>) -> Vec<TestDescAndFn> {
+    let mut expanded = Vec::with_capacity(tests.len());
+    for test in tests {
+        let TestDescAndFn { desc, testfn } = test;
+        let generator = match testfn {
+            StaticTestCasesFn(f) => f,
+            testfn => {
+                expanded.push(TestDescAndFn {
+                    desc: desc,
+                    testfn: testfn,
+                });
+                continue;
+            }
+        };
+
+        match panic::catch_unwind(generator) {
+            Ok(cases) => {
+                for (name, f) in cases {
+                    expanded.push(TestDescAndFn {
+                        desc: TestDesc {
+                            name: DynTestName(format!("{}::{}", desc.name, name)),
+                            ..desc.clone()
+                        },
+                        testfn: DynTestFn(Box::new(move || f())),
+                    });
+                }
+            }
+            Err(_) => {
+                expanded.push(TestDescAndFn {
+                    desc: TestDesc { should_panic: ShouldPanic::No, ..desc },
+                    testfn: DynTestFn(Box::new(|| panic!("generating the test cases panicked"))),
+                });
+            }
+        }
+    }
+    expanded
+}
+
+/// Keeps the tests of the `k`-th of `n` shards, counting from 1.
+///
+/// The shard of a test only depends on its name, so every runner agrees on
+/// the split no matter which other tests it sees.
+pub fn shard_tests(tests: Vec<TestDescAndFn>, k: usize, n: usize) -> Vec<TestDescAndFn> {
+    assert!(1 <= k && k <= n);
+    tests.into_iter()
+         .filter(|test| name_hash(test.desc.name.as_slice()) % (n as u64) == (k - 1) as u64)
+         .collect()
+}
+
+// 64-bit FNV-1a, which unlike `std`'s hashers is guaranteed to give the same
+// result on every platform and in every release.
+fn name_hash(name: &str) -> u64 {
+    let mut hash = 0xcbf29ce484222325;
+    for b in name.bytes() {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// Shuffles the tests into an order determined only by `seed`.
+pub fn shuffle_tests(tests: &mut [TestDescAndFn], seed: u64) {
+    // Fisher-Yates
+    let mut rng = SplitMix64(seed);
+    for i in (1..tests.len()).rev() {
+        let j = rng.below(i + 1);
+        tests.swap(i, j);
+    }
+}
+
+// The splitmix64 generator. It is plenty random for reordering tests and
+// resampling benchmark results, and lets us avoid a dependency on `rand`.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    // A number in `0..n`
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % (n as u64)) as usize
+    }
+}
+
+fn random_seed() -> u64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or(Duration::new(0, 0));
+    now.as_secs().wrapping_mul(1_000_000_000).wrapping_add(now.subsec_nanos() as u64)
+}
+
 pub fn convert_benchmarks_to_tests(tests: Vec<TestDescAndFn>) -> Vec<TestDescAndFn> {
     // convert benchmarks to tests, if we're not benchmarking them
     tests.into_iter()
@@ -1156,16 +2127,6 @@ pub fn run_test(opts: &TestOpts,
                       monitor_ch: Sender<MonitorMsg>,
                       nocapture: bool,
                       testfn: Box<FnBox() + Send>) {
-        struct Sink(Arc<Mutex<Vec<u8>>>);
-        impl Write for Sink {
-            fn write(&mut self, data: &[u8]) -> io::Result<usize> {
-                Write::write(&mut *self.0.lock().unwrap(), data)
-            }
-            fn flush(&mut self) -> io::Result<()> {
-                Ok(())
-            }
-        }
-
         thread::spawn(move || {
             let data = Arc::new(Mutex::new(Vec::new()));
             let data2 = data.clone();
@@ -1174,28 +2135,35 @@ pub fn run_test(opts: &TestOpts,
                 StaticTestName(name) => name.to_owned(),
             });
 
+            // The capture is inherited by any threads the test spawns, so
+            // their output ends up in the report of this test as well.
             let result_guard = cfg.spawn(move || {
                                       if !nocapture {
-                                          io::set_print(box Sink(data2.clone()));
-                                          io::set_panic(box Sink(data2));
+                                          io::set_output_capture(Some(data2));
                                       }
                                       testfn()
                                   })
                                   .unwrap();
-            let test_result = calc_result(&desc, result_guard.join());
+            let result = result_guard.join();
+            let test_result = calc_result(&desc, result.as_ref().map(|_| ()).map_err(|e| &**e));
             let stdout = data.lock().unwrap().to_vec();
             monitor_ch.send((desc.clone(), test_result, stdout)).unwrap();
         });
     }
 
+    let warm_up = opts.bench_warm_up.unwrap_or(Duration::new(0, 0));
+    let bench_time = opts.bench_time.unwrap_or(Duration::from_secs(BENCH_TIME_S));
+
     match testfn {
         DynBenchFn(bencher) => {
-            let bs = ::bench::benchmark(|harness| bencher.run(harness));
+            let bs = ::bench::benchmark_for(warm_up, bench_time, |harness| bencher.run(harness));
             monitor_ch.send((desc, TrBench(bs), Vec::new())).unwrap();
             return;
         }
         StaticBenchFn(benchfn) => {
-            let bs = ::bench::benchmark(|harness| (benchfn.clone())(harness));
+            let bs = ::bench::benchmark_for(warm_up,
+                                            bench_time,
+                                            |harness| (benchfn.clone())(harness));
             monitor_ch.send((desc, TrBench(bs), Vec::new())).unwrap();
             return;
         }
@@ -1211,16 +2179,214 @@ pub fn run_test(opts: &TestOpts,
             monitor_ch.send((desc, TrMetrics(mm), Vec::new())).unwrap();
             return;
         }
+        DynTestFn(_) | StaticTestFn(_) if opts.isolate => {
+            run_test_in_subprocess(desc, monitor_ch, opts.nocapture, opts.fail_time)
+        }
         DynTestFn(f) => run_test_inner(desc, monitor_ch, opts.nocapture, f),
         StaticTestFn(f) => run_test_inner(desc, monitor_ch, opts.nocapture, Box::new(f)),
+        StaticTestCasesFn(_) => panic!("test case generators have to be expanded before running"),
+    }
+}
+
+// Runs a single test by re-executing the current binary with
+// `ISOLATED_TEST_ENV` set, see `run_isolated_test` for the other half. The
+// exit status of the child decides the result of the test, so crashes and
+// aborts are reported as failures of just this test. A child still running
+// when `--fail-time` runs out is killed, and the test reported as failed.
+fn run_test_in_subprocess(desc: TestDesc,
+                          monitor_ch: Sender<MonitorMsg>,
+                          nocapture: bool,
+                          fail_time: Option<Duration>) {
+    thread::spawn(move || {
+        let mut cmd = match env::current_exe() {
+            Ok(exe) => Command::new(exe),
+            Err(e) => {
+                let msg = format!("failed to find the current executable: {}\n", e);
+                monitor_ch.send((desc, TrFailed, msg.into_bytes())).unwrap();
+                return;
+            }
+        };
+        cmd.env(ISOLATED_TEST_ENV, desc.name.as_slice());
+        if panic_aborts() {
+            cmd.env(ISOLATED_PANIC_ABORTS_ENV, "1");
+        }
+        if !nocapture {
+            cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+        }
+
+        let mut timed_out = false;
+        let (status, mut output) = match cmd.spawn() {
+            Ok(child) => {
+                let (done_tx, done_rx) = channel::<()>();
+                let watchdog = fail_time.map(|fail_time| {
+                    let pid = child.id();
+                    thread::spawn(move || {
+                        let timed_out = done_rx.recv_timeout(fail_time) ==
+                                        Err(RecvTimeoutError::Timeout);
+                        if timed_out {
+                            kill_process(pid);
+                        }
+                        timed_out
+                    })
+                });
+                let out = child.wait_with_output();
+                drop(done_tx);
+                if let Some(watchdog) = watchdog {
+                    timed_out = watchdog.join().unwrap_or(false);
+                }
+                match out {
+                    Ok(out) => {
+                        let mut output = out.stdout;
+                        output.extend_from_slice(&out.stderr);
+                        (Ok(out.status), output)
+                    }
+                    Err(e) => (Err(e), Vec::new()),
+                }
+            }
+            Err(e) => (Err(e), Vec::new()),
+        };
+
+        let result = match status {
+            _ if timed_out => {
+                let msg = format!("test did not finish within the time limit of {} seconds\n",
+                                  fail_time.unwrap().as_secs());
+                output.extend_from_slice(msg.as_bytes());
+                TrFailed
+            }
+            Ok(ref status) if status.success() => TrOk,
+            Ok(ref status) if status.code().is_some() => TrFailed,
+            Ok(status) => {
+                let msg = format!("test process terminated abnormally ({})\n", status);
+                output.extend_from_slice(msg.as_bytes());
+                TrFailed
+            }
+            Err(e) => {
+                let msg = format!("failed to run test process: {}\n", e);
+                output.extend_from_slice(msg.as_bytes());
+                TrFailed
+            }
+        };
+        monitor_ch.send((desc, result, output)).unwrap();
+    });
+}
+
+#[cfg(unix)]
+fn kill_process(pid: u32) {
+    unsafe {
+        libc::kill(pid as libc::pid_t, libc::SIGKILL);
+    }
+}
+
+#[cfg(windows)]
+#[allow(bad_style)]
+fn kill_process(pid: u32) {
+    type HANDLE = *mut u8;
+    const PROCESS_TERMINATE: u32 = 0x0001;
+    extern "system" {
+        fn OpenProcess(dwDesiredAccess: u32, bInheritHandle: i32, dwProcessId: u32) -> HANDLE;
+        fn TerminateProcess(hProcess: HANDLE, uExitCode: u32) -> i32;
+        fn CloseHandle(hObject: HANDLE) -> i32;
+    }
+    unsafe {
+        let process = OpenProcess(PROCESS_TERMINATE, 0, pid);
+        if !process.is_null() {
+            TerminateProcess(process, 101);
+            CloseHandle(process);
+        }
+    }
+}
+
+// Whether the test binary was compiled with `-C panic=abort`, in which case
+// isolated tests can't catch the panics of the test. This can't be found out
+// without panicking, so the binary is re-executed once to try.
+fn panic_aborts() -> bool {
+    // 0: not known yet, 1: panics unwind, 2: panics abort
+    static STRATEGY: AtomicUsize = ATOMIC_USIZE_INIT;
+    match STRATEGY.load(Ordering::SeqCst) {
+        1 => return false,
+        2 => return true,
+        _ => {}
+    }
+    let aborts = env::current_exe().and_then(|exe| {
+        Command::new(exe)
+            .env(PANIC_PROBE_ENV, "1")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+    }).map(|status| !status.success()).unwrap_or(false);
+    STRATEGY.store(if aborts { 2 } else { 1 }, Ordering::SeqCst);
+    aborts
+}
+
+// The other half of `panic_aborts`: exits successfully if a panic can be
+// caught.
+fn probe_panic_strategy() -> ! {
+    panic::set_hook(Box::new(|_| {}));
+    let _ = panic::catch_unwind(|| panic!());
+    process::exit(0)
+}
+
+// The entry point of a test binary which was re-executed to run the test
+// `name` on its own. The result is reported through the exit code.
+fn run_isolated_test(name: &str, tests: Vec<TestDescAndFn>) -> ! {
+    // Processes started by the test mustn't take themselves for isolated
+    // tests, the test may well run its own binary again.
+    env::remove_var(ISOLATED_TEST_ENV);
+    let panic_aborts = env::var_os(ISOLATED_PANIC_ABORTS_ENV).is_some();
+    env::remove_var(ISOLATED_PANIC_ABORTS_ENV);
+
+    // Only the generator of the test is run, if it's a generated test case.
+    let tests = tests.into_iter().filter(|t| {
+        match t.testfn {
+            StaticTestCasesFn(..) => name.starts_with(&format!("{}::", t.desc.name)),
+            _ => t.desc.name.as_slice() == name,
+        }
+    }).collect();
+    let test = match expand_test_cases(tests).into_iter().find(|t| t.desc.name.as_slice() == name) {
+        Some(test) => test,
+        None => panic!("couldn't find a test with the name `{}`", name),
+    };
+    let TestDescAndFn { desc, testfn } = test;
+    let testfn: Box<FnBox() + Send> = match testfn {
+        StaticTestFn(f) => Box::new(f),
+        DynTestFn(f) => f,
+        _ => panic!("`{}` is not a test and can't be run in its own process", name),
+    };
+
+    fn exit_code(result: TestResult) -> i32 {
+        if result == TrOk { 0 } else { 101 }
+    }
+
+    if panic_aborts {
+        // The process aborts right after a panic, so the result has to be
+        // decided in the panic hook. As without `--isolate`, only the panics
+        // of the test's own thread decide it.
+        thread_local!(static IS_TEST_THREAD: Cell<bool> = Cell::new(false));
+        IS_TEST_THREAD.with(|t| t.set(true));
+        let builtin_panic_hook = panic::take_hook();
+        let hook_desc = desc.clone();
+        panic::set_hook(Box::new(move |info| {
+            if !IS_TEST_THREAD.with(|t| t.get()) {
+                return builtin_panic_hook(info);
+            }
+            let result = calc_result(&hook_desc, Err(info.payload()));
+            if result != TrOk {
+                builtin_panic_hook(info);
+            }
+            process::exit(exit_code(result));
+        }));
     }
+
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(move || testfn()));
+    let result = calc_result(&desc, result.as_ref().map(|_| ()).map_err(|e| &**e));
+    process::exit(exit_code(result))
 }
 
-fn calc_result(desc: &TestDesc, task_result: Result<(), Box<Any + Send>>) -> TestResult {
+fn calc_result(desc: &TestDesc, task_result: Result<(), &(Any + Send)>) -> TestResult {
     match (&desc.should_panic, task_result) {
         (&ShouldPanic::No, Ok(())) |
         (&ShouldPanic::Yes, Err(_)) => TrOk,
-        (&ShouldPanic::YesWithMessage(msg), Err(ref err))
+        (&ShouldPanic::YesWithMessage(msg), Err(err))
             if err.downcast_ref::<String>()
                .map(|e| &**e)
                .or_else(|| err.downcast_ref::<&'static str>().map(|e| *e))
@@ -1323,13 +2489,28 @@ impl Bencher {
     }
 
     // This is a more statistics-driven benchmark algorithm
-    pub fn auto_bench<F>(&mut self, mut f: F) -> stats::Summary
+    pub fn auto_bench<F>(&mut self, f: F) -> stats::Summary
+        where F: FnMut(&mut Bencher)
+    {
+        self.auto_bench_samples(f).0
+    }
+
+    // Like `auto_bench`, but also returns the (winsorized) per-iteration
+    // times the summary was computed from.
+    fn auto_bench_samples<F>(&mut self, mut f: F) -> (stats::Summary, Vec<f64>)
         where F: FnMut(&mut Bencher)
     {
         // Initial bench run to get ballpark figure.
         let mut n = 1;
         self.bench_n(n, |x| f(x));
 
+        // Get caches, branch predictors and CPU frequency into a steady
+        // state before measuring anything.
+        let warm_up_start = Instant::now();
+        while warm_up_start.elapsed() < self.warm_up {
+            self.bench_n(n, |x| f(x));
+        }
+
         // Try to estimate iter count for 1ms falling back to 1m
         // iterations if first run took < 1ns.
         if self.ns_per_iter() == 0 {
@@ -1372,13 +2553,13 @@ impl Bencher {
             // stable median.
             if loop_run > Duration::from_millis(100) && summ.median_abs_dev_pct < 1.0 &&
                summ.median - summ5.median < summ5.median_abs_dev {
-                return summ5;
+                return (summ5, samples.to_vec());
             }
 
             total_run = total_run + loop_run;
-            // Longest we ever run for is 3s.
-            if total_run > Duration::from_secs(3) {
-                return summ5;
+            // Longest we ever run for is `max_time`, 3s by default.
+            if total_run > self.max_time {
+                return (summ5, samples.to_vec());
             }
 
             // If we overflow here just return the results so far. We check a
@@ -1387,7 +2568,7 @@ impl Bencher {
             // the summ5 result)
             n = match n.checked_mul(10) {
                 Some(_) => n * 2,
-                None => return summ5,
+                None => return (summ5, samples.to_vec()),
             };
         }
     }
@@ -1396,24 +2577,35 @@ impl Bencher {
 pub mod bench {
     use std::cmp;
     use std::time::Duration;
-    use super::{Bencher, BenchSamples};
+    use super::{Bencher, BenchSamples, BENCH_TIME_S};
 
     pub fn benchmark<F>(f: F) -> BenchSamples
         where F: FnMut(&mut Bencher)
+    {
+        benchmark_for(Duration::new(0, 0), Duration::from_secs(BENCH_TIME_S), f)
+    }
+
+    /// Benchmarks `f` after running it for `warm_up` first, measuring it
+    /// for at most `max_time`.
+    pub fn benchmark_for<F>(warm_up: Duration, max_time: Duration, f: F) -> BenchSamples
+        where F: FnMut(&mut Bencher)
     {
         let mut bs = Bencher {
             iterations: 0,
             dur: Duration::new(0, 0),
+            warm_up: warm_up,
+            max_time: max_time,
             bytes: 0,
         };
 
-        let ns_iter_summ = bs.auto_bench(f);
+        let (ns_iter_summ, ns_iter_samples) = bs.auto_bench_samples(f);
 
         let ns_iter = cmp::max(ns_iter_summ.median as u64, 1);
         let mb_s = bs.bytes * 1000 / ns_iter;
 
         BenchSamples {
             ns_iter_summ: ns_iter_summ,
+            ns_iter_samples: ns_iter_samples,
             mb_s: mb_s as usize,
         }
     }
@@ -1424,6 +2616,8 @@ pub mod bench {
         let mut bs = Bencher {
             iterations: 0,
             dur: Duration::new(0, 0),
+            warm_up: Duration::new(0, 0),
+            max_time: Duration::from_secs(BENCH_TIME_S),
             bytes: 0,
         };
         bs.bench_n(1, f);
@@ -1433,8 +2627,15 @@ pub mod bench {
 #[cfg(test)]
 mod tests {
     use test::{TrFailed, TrIgnored, TrOk, filter_tests, parse_opts, TestDesc, TestDescAndFn,
-               TestOpts, run_test, MetricMap, StaticTestName, DynTestName, DynTestFn, ShouldPanic};
+               TestOpts, run_test, MetricMap, StaticTestName, DynTestName, DynTestFn, ShouldPanic,
+               StaticTestCasesFn, StaticBenchFn, run_tests_console};
+    use {shard_tests, shuffle_tests, load_baseline};
+    use Bencher;
+    use std::env;
+    use std::fs;
+    use std::sync::atomic::{AtomicBool, Ordering, ATOMIC_BOOL_INIT};
     use std::sync::mpsc::channel;
+    use std::time::Duration;
 
     #[test]
     pub fn do_not_run_ignored_tests() {
@@ -1546,6 +2747,53 @@ mod tests {
         assert!(res == TrFailed);
     }
 
+    #[test]
+    fn test_fail_time_fails_hung_test() {
+        let mut opts = TestOpts::new();
+        opts.run_tests = true;
+        opts.test_threads = Some(1);
+        opts.fail_time = Some(Duration::from_secs(0));
+
+        // The test blocks until the channel is closed at the end, so its
+        // thread doesn't outlive this test.
+        let (tx, rx) = channel::<()>();
+        let desc = TestDescAndFn {
+            desc: TestDesc {
+                name: StaticTestName("whatever"),
+                ignore: false,
+                should_panic: ShouldPanic::No,
+            },
+            testfn: DynTestFn(Box::new(move || { let _ = rx.recv(); })),
+        };
+        let mut results = Vec::new();
+        super::run_tests(&opts, vec![desc], |event| {
+            if let super::TestEvent::TeResult(_, res, _, _) = event {
+                results.push(res);
+            }
+            Ok(())
+        }).unwrap();
+        drop(tx);
+        assert!(results == vec![TrFailed]);
+    }
+
+    #[test]
+    fn parse_time_flags() {
+        let args = vec!["progname".to_string(),
+                        "--report-time".to_string(),
+                        "--slowest".to_string(),
+                        "5".to_string(),
+                        "--fail-time".to_string(),
+                        "30".to_string()];
+        let opts = match parse_opts(&args) {
+            Some(Ok(o)) => o,
+            _ => panic!("Malformed arg in parse_time_flags"),
+        };
+        assert!(opts.report_time);
+        assert_eq!(opts.slowest, Some(5));
+        assert_eq!(opts.warn_time, None);
+        assert_eq!(opts.fail_time, Some(Duration::from_secs(30)));
+    }
+
     #[test]
     fn parse_ignored_flag() {
         let args = vec!["progname".to_string(), "filter".to_string(), "--ignored".to_string()];
@@ -1588,6 +2836,191 @@ mod tests {
         assert!(!filtered[0].desc.ignore);
     }
 
+    fn named_tests(names: &[&'static str]) -> Vec<TestDescAndFn> {
+        names.iter()
+             .map(|name| {
+                 TestDescAndFn {
+                     desc: TestDesc {
+                         name: StaticTestName(*name),
+                         ignore: false,
+                         should_panic: ShouldPanic::No,
+                     },
+                     testfn: DynTestFn(Box::new(move || {})),
+                 }
+             })
+             .collect()
+    }
+
+    fn filtered_names(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> Vec<String> {
+        filter_tests(opts, tests).into_iter().map(|t| t.desc.name.to_string()).collect()
+    }
+
+    #[test]
+    pub fn multiple_filters_and_exact() {
+        let tests = || named_tests(&["base", "base::test", "base::test1", "base::test2"]);
+
+        let mut opts = TestOpts::new();
+        opts.filters = vec!["test1".to_string(), "test2".to_string()];
+        assert_eq!(filtered_names(&opts, tests()), vec!["base::test1", "base::test2"]);
+
+        opts.filters = vec!["base".to_string(), "base::test".to_string()];
+        opts.filter_exact = true;
+        assert_eq!(filtered_names(&opts, tests()), vec!["base", "base::test"]);
+
+        opts.filters = vec!["test".to_string()];
+        assert!(filtered_names(&opts, tests()).is_empty());
+    }
+
+    #[test]
+    pub fn skip_filters() {
+        let tests = || named_tests(&["base", "base::test", "base::test1", "base::test2"]);
+
+        let mut opts = TestOpts::new();
+        opts.skip = vec!["test1".to_string(), "test2".to_string()];
+        assert_eq!(filtered_names(&opts, tests()), vec!["base", "base::test"]);
+
+        opts.skip = vec!["base::test".to_string()];
+        opts.filter_exact = true;
+        assert_eq!(filtered_names(&opts, tests()), vec!["base", "base::test1", "base::test2"]);
+    }
+
+    #[test]
+    fn parse_filters_and_skip() {
+        let args = vec!["progname".to_string(),
+                        "a".to_string(),
+                        "b".to_string(),
+                        "--skip".to_string(),
+                        "c".to_string(),
+                        "--skip".to_string(),
+                        "d".to_string(),
+                        "--exact".to_string()];
+        let opts = match parse_opts(&args) {
+            Some(Ok(o)) => o,
+            _ => panic!("Malformed arg in parse_filters_and_skip"),
+        };
+        assert_eq!(opts.filters, vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(opts.skip, vec!["c".to_string(), "d".to_string()]);
+        assert!(opts.filter_exact);
+    }
+
+    #[test]
+    pub fn test_cases_are_expanded() {
+        fn cases() -> Vec<(String, Box<Fn() + Send>)> {
+            vec![("one".to_string(), Box::new(|| {}) as Box<Fn() + Send>),
+                 ("two".to_string(), Box::new(|| panic!()) as Box<Fn() + Send>)]
+        }
+        let tests = vec![TestDescAndFn {
+                             desc: TestDesc {
+                                 name: StaticTestName("gen"),
+                                 ignore: false,
+                                 should_panic: ShouldPanic::No,
+                             },
+                             testfn: StaticTestCasesFn(cases),
+                         }];
+        let mut opts = TestOpts::new();
+        opts.filters = vec!["gen::t".to_string()];
+        let filtered = filter_tests(&opts, tests);
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].desc.name.to_string(), "gen::two");
+
+        let (tx, rx) = channel();
+        run_test(&opts, false, filtered.into_iter().next().unwrap(), tx);
+        let (_, res, _) = rx.recv().unwrap();
+        assert!(res == TrFailed);
+    }
+
+    #[test]
+    pub fn filtered_out_test_cases_are_not_generated() {
+        static GENERATED: AtomicBool = ATOMIC_BOOL_INIT;
+        fn cases() -> Vec<(String, Box<Fn() + Send>)> {
+            GENERATED.store(true, Ordering::SeqCst);
+            Vec::new()
+        }
+        let generator = || vec![TestDescAndFn {
+            desc: TestDesc {
+                name: StaticTestName("gen"),
+                ignore: false,
+                should_panic: ShouldPanic::No,
+            },
+            testfn: StaticTestCasesFn(cases),
+        }];
+
+        let mut opts = TestOpts::new();
+        opts.filters = vec!["other".to_string()];
+        opts.filter_exact = true;
+        assert!(filter_tests(&opts, generator()).is_empty());
+
+        let mut opts = TestOpts::new();
+        opts.skip = vec!["ge".to_string()];
+        assert!(filter_tests(&opts, generator()).is_empty());
+
+        let mut opts = TestOpts::new();
+        opts.run_ignored = true;
+        assert!(filter_tests(&opts, generator()).is_empty());
+
+        assert!(!GENERATED.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    pub fn saving_a_filtered_baseline_keeps_other_benchmarks() {
+        fn bench(b: &mut Bencher) {
+            b.iter(|| 1)
+        }
+        let benches = || {
+            vec!["a", "b"].into_iter().map(|name| {
+                TestDescAndFn {
+                    desc: TestDesc {
+                        name: StaticTestName(name),
+                        ignore: false,
+                        should_panic: ShouldPanic::No,
+                    },
+                    testfn: StaticBenchFn(bench),
+                }
+            }).collect::<Vec<_>>()
+        };
+        let dir = env::temp_dir().join("rust-libtest-saving-a-filtered-baseline");
+        let _ = fs::remove_dir_all(&dir);
+
+        for filter in &["a", "b"] {
+            let mut opts = TestOpts::new();
+            opts.bench_benchmarks = true;
+            opts.bench_time = Some(Duration::from_secs(0));
+            opts.baseline_dir = Some(dir.clone());
+            opts.save_baseline = Some("main".to_string());
+            opts.filters = vec![filter.to_string()];
+            opts.filter_exact = true;
+            assert!(run_tests_console(&opts, benches()).unwrap());
+        }
+
+        let baseline = load_baseline(&dir.join("main.txt")).unwrap();
+        assert_eq!(baseline.keys().collect::<Vec<_>>(), ["a", "b"]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    pub fn shards_cover_all_tests_once() {
+        let names = ["a", "b", "c", "d", "e", "f", "g", "h", "i", "j"];
+        let mut sharded = Vec::new();
+        for k in 1..4 {
+            let shard = shard_tests(named_tests(&names), k, 3);
+            sharded.extend(filtered_names(&TestOpts::new(), shard));
+        }
+        sharded.sort();
+        assert_eq!(sharded, names);
+    }
+
+    #[test]
+    pub fn shuffle_is_replayable() {
+        let names = ["a", "b", "c", "d", "e", "f", "g", "h", "i", "j"];
+        let shuffled = |seed| {
+            let mut tests = named_tests(&names);
+            shuffle_tests(&mut tests, seed);
+            tests.into_iter().map(|t| t.desc.name.to_string()).collect::<Vec<_>>()
+        };
+        assert_eq!(shuffled(42), shuffled(42));
+        assert!(shuffled(42) != shuffled(43));
+    }
+
     #[test]
     pub fn sort_tests() {
         let mut opts = TestOpts::new();
@@ -13,6 +13,7 @@
 
 use std::cmp::Ordering::{self, Equal, Greater, Less};
 use std::mem;
+use SplitMix64;
 
 fn local_cmp(x: f64, y: f64) -> Ordering {
     // arbitrarily decide that NaNs are larger than everything.
@@ -325,12 +326,121 @@ pub fn winsorize(samples: &mut [f64], pct: f64) {
     }
 }
 
+/// The outcome of comparing a set of samples with an earlier, baseline, set.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Comparison {
+    /// Relative change of the median, e.g. `0.05` if the new median is 5% larger.
+    pub change: f64,
+    /// 95% confidence interval of `change`, estimated by bootstrapping.
+    pub change_ci: (f64, f64),
+    /// Two-sided p-value of a Mann-Whitney U test of both sample sets coming
+    /// from the same distribution.
+    ///
+    /// See: https://en.wikipedia.org/wiki/Mann%E2%80%93Whitney_U_test
+    pub p_value: f64,
+}
+
+impl Comparison {
+    /// Compare `new` samples against the `old` ones. Both must be non-empty.
+    pub fn new(old: &[f64], new: &[f64]) -> Comparison {
+        Comparison {
+            change: new.median() / old.median() - 1.0,
+            change_ci: bootstrap_change_ci(old, new),
+            p_value: mann_whitney_p(old, new),
+        }
+    }
+
+    /// Whether the change is significant at the 5% level, that is both the
+    /// test rejects the samples being alike and the confidence interval
+    /// doesn't include zero.
+    pub fn is_significant(&self) -> bool {
+        self.p_value < 0.05 && (self.change_ci.0 > 0.0 || self.change_ci.1 < 0.0)
+    }
+
+    /// Describes the change, taking smaller values (such as times) as better.
+    pub fn verdict(&self) -> &'static str {
+        if !self.is_significant() {
+            "no change"
+        } else if self.change > 0.0 {
+            "regressed"
+        } else {
+            "improved"
+        }
+    }
+}
+
+fn bootstrap_change_ci(old: &[f64], new: &[f64]) -> (f64, f64) {
+    const RESAMPLES: usize = 1000;
+
+    // A fixed seed, so that comparing the same samples twice gives the same
+    // interval.
+    let mut rng = SplitMix64(0x5eed);
+    let mut old_resample = vec![0.0; old.len()];
+    let mut new_resample = vec![0.0; new.len()];
+    let mut changes = Vec::with_capacity(RESAMPLES);
+    for _ in 0..RESAMPLES {
+        for x in &mut old_resample {
+            *x = old[rng.below(old.len())];
+        }
+        for x in &mut new_resample {
+            *x = new[rng.below(new.len())];
+        }
+        changes.push(new_resample.median() / old_resample.median() - 1.0);
+    }
+    local_sort(&mut changes);
+    (percentile_of_sorted(&changes, 2.5), percentile_of_sorted(&changes, 97.5))
+}
+
+// Uses the normal approximation of the U statistic, which is good enough for
+// the dozens of samples a benchmark produces.
+fn mann_whitney_p(old: &[f64], new: &[f64]) -> f64 {
+    let mut all: Vec<(f64, bool)> = old.iter()
+                                       .map(|&x| (x, false))
+                                       .chain(new.iter().map(|&x| (x, true)))
+                                       .collect();
+    all.sort_by(|a, b| local_cmp(a.0, b.0));
+
+    // Sum of the ranks of the new samples, giving tied samples the average
+    // of their ranks.
+    let mut rank_sum = 0.0;
+    let mut i = 0;
+    while i < all.len() {
+        let mut j = i;
+        while j + 1 < all.len() && all[j + 1].0 == all[i].0 {
+            j += 1;
+        }
+        let rank = (i + j) as f64 / 2.0 + 1.0;
+        rank_sum += rank * all[i..j + 1].iter().filter(|x| x.1).count() as f64;
+        i = j + 1;
+    }
+
+    let (n1, n2) = (new.len() as f64, old.len() as f64);
+    let u = rank_sum - n1 * (n1 + 1.0) / 2.0;
+    let mean = n1 * n2 / 2.0;
+    let sd = (n1 * n2 * (n1 + n2 + 1.0) / 12.0).sqrt();
+    if sd == 0.0 {
+        return 1.0;
+    }
+    let z = (u - mean).abs() / sd;
+    erfc(z / 2f64.sqrt())
+}
+
+// Complementary error function for `x >= 0`, with an absolute error below
+// 1.5e-7. Abramowitz and Stegun, formula 7.1.26.
+fn erfc(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.3275911 * x);
+    let poly = t * (0.254829592 +
+                    t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    poly * (-x * x).exp()
+}
+
 // Test vectors generated from R, using the script src/etc/stat-test-vectors.r.
 
 #[cfg(test)]
 mod tests {
     use stats::Stats;
     use stats::Summary;
+    use stats::Comparison;
     use std::f64;
     use std::io::prelude::*;
     use std::io;
@@ -874,6 +984,27 @@ mod tests {
     fn test_sum_f64_between_ints_that_sum_to_0() {
         assert_eq!([1e30f64, 1.2f64, -1e30f64].sum(), 1.2);
     }
+
+    #[test]
+    fn test_compare_same_samples() {
+        let old: Vec<f64> = (0..50).map(|i| 1000.0 + (i % 10) as f64).collect();
+        let cmp = Comparison::new(&old, &old);
+        assert_eq!(cmp.change, 0.0);
+        assert!(cmp.change_ci.0 <= 0.0 && 0.0 <= cmp.change_ci.1);
+        assert!(cmp.p_value > 0.9);
+        assert_eq!(cmp.verdict(), "no change");
+    }
+    #[test]
+    fn test_compare_shifted_samples() {
+        let old: Vec<f64> = (0..50).map(|i| 1000.0 + (i % 10) as f64).collect();
+        let new: Vec<f64> = old.iter().map(|x| x * 1.1).collect();
+        let cmp = Comparison::new(&old, &new);
+        assert_approx_eq!(cmp.change, 0.1);
+        assert!(cmp.change_ci.0 > 0.0);
+        assert!(cmp.p_value < 0.001);
+        assert_eq!(cmp.verdict(), "regressed");
+        assert_eq!(Comparison::new(&new, &old).verdict(), "improved");
+    }
 }
 
 #[cfg(test)]
@@ -13,16 +13,10 @@ const C: i32 = 2;
 
 const CR: &'static mut i32 = &mut C; //~ ERROR E0017
                                      //~| NOTE constants require immutable values
-                                     //~| ERROR E0017
-                                     //~| NOTE constants require immutable values
 static STATIC_REF: &'static mut i32 = &mut X; //~ ERROR E0017
-                                              //~| NOTE statics require immutable values
-                                              //~| ERROR E0017
                                               //~| NOTE statics require immutable values
                                               //~| ERROR E0388
                                               //~| NOTE cannot write data in a static definition
 static CONST_REF: &'static mut i32 = &mut C; //~ ERROR E0017
                                              //~| NOTE statics require immutable values
-                                             //~| ERROR E0017
-                                             //~| NOTE statics require immutable values
 fn main() {}
@@ -0,0 +1,21 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z error-code-limit=1
+
+// Only the first error of each error code is reported, the others are
+// summarized in a note without a span.
+
+fn main() {
+    let _a: u32 = "a"; //~ ERROR E0308
+    let _b: u32 = "b";
+    let _c: u32 = "c";
+    let _d = 1u32 + "d"; //~ ERROR E0277
+}
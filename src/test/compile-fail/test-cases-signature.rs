@@ -0,0 +1,22 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: --test
+
+//! Test that wrongly-typed test case generators aren't ignored
+
+#![feature(test_cases)]
+
+#[test_cases]
+fn foo() { } //~ ERROR functions used as test case generators
+
+#[test_cases]
+fn bar(x: isize) -> Vec<(String, Box<Fn() + Send>)> { vec![] }
+//~^ ERROR functions used as test case generators
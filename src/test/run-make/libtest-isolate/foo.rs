@@ -0,0 +1,47 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::env;
+use std::panic;
+use std::process;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn catches_own_panic() {
+    assert!(panic::catch_unwind(|| panic!("caught")).is_err());
+}
+
+#[test]
+fn thread_panics() {
+    assert!(thread::spawn(|| panic!("in a thread")).join().is_err());
+}
+
+#[test]
+#[should_panic]
+fn panics() {
+    panic!("expected");
+}
+
+#[test]
+fn environment_is_cleared() {
+    // Otherwise a test running its own binary would run this test again.
+    assert!(env::vars().all(|(name, _)| !name.starts_with("__RUST_TEST_ISOLATED")));
+}
+
+#[test]
+fn aborts() {
+    process::abort();
+}
+
+#[test]
+fn hangs() {
+    thread::sleep(Duration::from_secs(3600));
+}
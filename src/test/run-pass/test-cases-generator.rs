@@ -0,0 +1,31 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: --test
+// ignore-pretty: does not work well with `--test`
+
+#![feature(test_cases)]
+
+#[test_cases]
+fn squares() -> Vec<(String, Box<Fn() + Send>)> {
+    (0..4u32).map(|i| {
+        let case: Box<Fn() + Send> = Box::new(move || assert_eq!(i * i, i.pow(2)));
+        (format!("square_{}", i), case)
+    }).collect()
+}
+
+#[test_cases]
+#[should_panic(expected = "odd")]
+fn evens() -> Vec<(String, Box<Fn() + Send>)> {
+    vec![1, 3, 5].into_iter().map(|i| {
+        let case: Box<Fn() + Send> = Box::new(move || assert!(i % 2 == 0, "odd"));
+        (i.to_string(), case)
+    }).collect()
+}
@@ -0,0 +1,20 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// The suggestion to add `move` to a closure escaping its frame (E0373) is
+// machine-applicable.
+
+fn adder(x: u32) -> Box<Fn(u32) -> u32> {
+    Box::new(|y| x + y)
+}
+
+fn main() {
+    assert_eq!(adder(1)(2), 3);
+}
@@ -30,6 +30,7 @@ pub enum Mode {
     RunMake,
     Ui,
     MirOpt,
+    RunRustfix,
 }
 
 impl FromStr for Mode {
@@ -51,6 +52,7 @@ impl FromStr for Mode {
             "run-make" => Ok(RunMake),
             "ui" => Ok(Ui),
             "mir-opt" => Ok(MirOpt),
+            "run-rustfix" => Ok(RunRustfix),
             _ => Err(()),
         }
     }
@@ -74,6 +76,7 @@ impl fmt::Display for Mode {
                               RunMake => "run-make",
                               Ui => "ui",
                               MirOpt => "mir-opt",
+                              RunRustfix => "run-rustfix",
                           },
                           f)
     }
@@ -124,6 +127,10 @@ pub struct Config {
     // Run ignored tests
     pub run_ignored: bool,
 
+    // Overwrite expected output files with the actual output instead of
+    // failing the test
+    pub bless: bool,
+
     // Only run tests that match this filter
     pub filter: Option<String>,
 
@@ -22,6 +22,7 @@ pub enum ErrorKind {
     Error,
     Note,
     Suggestion,
+    Replacement,
     Warning,
 }
 
@@ -35,6 +36,7 @@ impl FromStr for ErrorKind {
             "ERROR" => Ok(ErrorKind::Error),
             "NOTE" => Ok(ErrorKind::Note),
             "SUGGESTION" => Ok(ErrorKind::Suggestion),
+            "REPLACE" => Ok(ErrorKind::Replacement),
             "WARN" => Ok(ErrorKind::Warning),
             "WARNING" => Ok(ErrorKind::Warning),
             _ => Err(()),
@@ -49,6 +51,7 @@ impl fmt::Display for ErrorKind {
             ErrorKind::Error => write!(f, "error"),
             ErrorKind::Note => write!(f, "note"),
             ErrorKind::Suggestion => write!(f, "suggestion"),
+            ErrorKind::Replacement => write!(f, "replacement"),
             ErrorKind::Warning => write!(f, "warning"),
         }
     }
@@ -60,9 +63,54 @@ pub struct Error {
     /// What kind of message we expect (e.g. warning, error, suggestion).
     /// `None` if not specified or unknown message kind.
     pub kind: Option<ErrorKind>,
+    /// The columns on `line_num` the message points at. Only checked for
+    /// expected messages that were given an `@COL` or `@COL..COL` span.
+    pub col_span: Option<ColumnSpan>,
     pub msg: String,
 }
 
+impl Error {
+    /// Whether `actual`, as reported by the compiler, is the message this
+    /// expected error describes.
+    pub fn matches(&self, actual: &Error) -> bool {
+        self.line_num == actual.line_num &&
+            (self.kind.is_none() || actual.kind == self.kind) &&
+            self.col_span.map_or(true, |span| {
+                actual.col_span.map_or(false, |actual_span| span.matches(&actual_span))
+            }) &&
+            if self.kind == Some(ErrorKind::Replacement) {
+                // Replacements are source code, so a substring isn't good enough.
+                actual.msg == self.msg
+            } else {
+                actual.msg.contains(&self.msg)
+            }
+    }
+}
+
+/// A range of 1-based columns. Like the `column_end` of the compiler's JSON
+/// spans, `end` is exclusive.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColumnSpan {
+    pub start: usize,
+    /// `None` if only the start column is to be checked.
+    pub end: Option<usize>,
+}
+
+impl ColumnSpan {
+    pub fn matches(&self, actual: &ColumnSpan) -> bool {
+        self.start == actual.start && (self.end.is_none() || self.end == actual.end)
+    }
+}
+
+impl fmt::Display for ColumnSpan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.end {
+            Some(end) => write!(f, "{}..{}", self.start, end),
+            None => write!(f, "{}", self.start),
+        }
+    }
+}
+
 #[derive(PartialEq, Debug)]
 enum WhichLine {
     ThisLine,
@@ -78,6 +126,17 @@ enum WhichLine {
 /// and also //~^ ERROR message one for the preceding line, and
 ///          //~| ERROR message two for that same line.
 ///
+/// The kind may be followed by a column span, `@COL` or `@START..END`, in
+/// which case the message must also point at those columns of the line:
+///
+///     //~^ ERROR @13..16 mismatched types
+///     //~| NOTE @9 expected because of this
+///
+/// Notes and helps without a span of their own are reported at the primary
+/// span of their parent, so a column span also checks which one they belong
+/// to. `//~ REPLACE @START..END TEXT` expects a suggestion to replace those
+/// columns with exactly `TEXT`.
+///
 /// If cfg is not None (i.e., in an incremental test), then we look
 /// for `//[X]~` instead, where `X` is the current `cfg`.
 pub fn load_errors(testfile: &Path, cfg: Option<&str>) -> Vec<Error> {
@@ -150,7 +209,8 @@ fn parse_expected(last_nonfollow_error: Option<usize>,
                 .collect::<String>();
         }
     }
-    let msg = msg.trim().to_owned();
+    let (col_span, msg) = parse_col_span(msg.trim());
+    let msg = msg.to_owned();
 
     let (which, line_num) = if follow {
         assert!(adjusts == 0, "use either //~| or //~^, not both.");
@@ -167,16 +227,36 @@ fn parse_expected(last_nonfollow_error: Option<usize>,
         (which, line_num)
     };
 
-    debug!("line={} tag={:?} which={:?} kind={:?} msg={:?}",
+    debug!("line={} tag={:?} which={:?} kind={:?} col_span={:?} msg={:?}",
            line_num,
            tag,
            which,
            kind,
+           col_span,
            msg);
     Some((which,
           Error {
         line_num: line_num,
         kind: kind,
+        col_span: col_span,
         msg: msg,
     }))
 }
+
+/// Splits a leading `@COL` or `@START..END` off an expected message.
+fn parse_col_span(msg: &str) -> (Option<ColumnSpan>, &str) {
+    if !msg.starts_with('@') {
+        return (None, msg);
+    }
+    let len = msg.find(char::is_whitespace).unwrap_or(msg.len());
+    let mut bounds = msg[1..len].splitn(2, "..");
+    let start = match bounds.next().unwrap().parse() {
+        Ok(start) => start,
+        // Not a column span, just a message starting with `@`.
+        Err(_) => return (None, msg),
+    };
+    let end = bounds.next().map(|end| {
+        end.parse().unwrap_or_else(|_| panic!("invalid column span `{}`", &msg[..len]))
+    });
+    (Some(ColumnSpan { start: start, end: end }), msg[len..].trim())
+}
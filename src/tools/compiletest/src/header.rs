@@ -46,7 +46,9 @@ impl EarlyProps {
                  parse_name_directive(ln, "ignore-cross-compile")) ||
                 ignore_gdb(config, ln) ||
                 ignore_lldb(config, ln) ||
-                ignore_llvm(config, ln);
+                ignore_llvm(config, ln) ||
+                ignore_only(config, ln) ||
+                ignore_needs(config, ln);
 
             props.should_fail = props.should_fail || parse_name_directive(ln, "should-fail");
         });
@@ -127,7 +129,7 @@ impl EarlyProps {
                         .expect("Malformed llvm version directive");
                     // Ignore if actual version is smaller the minimum required
                     // version
-                    &actual_version[..] < min_version
+                    llvm_version_to_int(actual_version) < llvm_version_to_int(min_version)
                 } else {
                     false
                 }
@@ -135,6 +137,33 @@ impl EarlyProps {
                 false
             }
         }
+
+        // `only-X` ignores the test unless `X` names the target's OS,
+        // architecture, environment, pointer width (`32bit` or `64bit`),
+        // the full target triple or the stage.
+        fn ignore_only(config: &Config, line: &str) -> bool {
+            let name = match parse_name_prefix(line, "only-") {
+                Some(name) => name,
+                None => return false,
+            };
+            name != util::get_os(&config.target) &&
+                name != util::get_arch(&config.target) &&
+                Some(name) != util::get_env(&config.target) &&
+                name != util::get_pointer_width(&config.target) &&
+                name != config.target &&
+                name != config.stage_id.split('-').next().unwrap()
+        }
+
+        // `needs-X` ignores the test unless the target has capability `X`.
+        fn ignore_needs(config: &Config, line: &str) -> bool {
+            match parse_name_prefix(line, "needs-") {
+                Some("unwind") => config.target.contains("emscripten"),
+                // These runtimes aren't built by this tree yet.
+                Some("sanitizer-support") | Some("profiler-support") => true,
+                Some(capability) => panic!("unknown capability in `needs-{}`", capability),
+                None => false,
+            }
+        }
     }
 }
 
@@ -455,6 +484,17 @@ fn parse_name_directive(line: &str, directive: &str) -> bool {
     line.contains(directive) && !line.contains(&("no-".to_owned() + directive))
 }
 
+/// Returns `X` for a directive of the form `PREFIXX`, e.g. `x86_64` for
+/// `only-x86_64` with the prefix `only-`.
+fn parse_name_prefix<'a>(line: &'a str, prefix: &str) -> Option<&'a str> {
+    let line = line.trim();
+    if line.starts_with(prefix) {
+        line[prefix.len()..].split_whitespace().next()
+    } else {
+        None
+    }
+}
+
 pub fn parse_name_value_directive(line: &str, directive: &str) -> Option<String> {
     let keycolon = format!("{}:", directive);
     if let Some(colon) = line.find(&keycolon) {
@@ -483,6 +523,23 @@ pub fn gdb_version_to_int(version_string: &str) -> isize {
     return major * 1000 + minor;
 }
 
+pub fn llvm_version_to_int(version_string: &str) -> isize {
+    let error_string = format!("Encountered LLVM version string with unexpected format: {}",
+                               version_string);
+
+    // Versions look like `3.9`, `3.9.1` or `4.0.0svn`; missing components
+    // count as zero and trailing suffixes are ignored.
+    let mut components = version_string.trim().split('.').map(|c| {
+        let digits = c.chars().take_while(|c| c.is_digit(10)).collect::<String>();
+        digits.parse::<isize>().ok()
+    });
+    let major = components.next().and_then(|c| c).expect(&error_string);
+    let minor = components.next().and_then(|c| c).unwrap_or(0);
+    let patch = components.next().and_then(|c| c).unwrap_or(0);
+
+    return major * 1000000 + minor * 1000 + patch;
+}
+
 pub fn lldb_version_to_int(version_string: &str) -> isize {
     let error_string = format!("Encountered LLDB version string with unexpected format: {}",
                                version_string);
@@ -490,3 +547,23 @@ pub fn lldb_version_to_int(version_string: &str) -> isize {
     let major: isize = version_string.parse().ok().expect(&error_string);
     return major;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::llvm_version_to_int;
+
+    #[test]
+    fn llvm_versions_compare_numerically() {
+        assert_eq!(llvm_version_to_int("3.9"), 3009000);
+        assert_eq!(llvm_version_to_int("3.9.1"), 3009001);
+        assert_eq!(llvm_version_to_int("4.0.0svn"), 4000000);
+        assert!(llvm_version_to_int("3.10") > llvm_version_to_int("3.9.1"));
+        assert!(llvm_version_to_int("3.9") < llvm_version_to_int("3.9.1"));
+    }
+
+    #[test]
+    #[should_panic]
+    fn llvm_version_needs_a_major_version() {
+        llvm_version_to_int("svn");
+    }
+}
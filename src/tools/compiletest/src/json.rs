@@ -8,7 +8,7 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use errors::{Error, ErrorKind};
+use errors::{ColumnSpan, Error, ErrorKind};
 use rustc_serialize::json;
 use std::str::FromStr;
 use std::path::Path;
@@ -36,9 +36,22 @@ struct DiagnosticSpan {
     column_end: usize,
     is_primary: bool,
     label: Option<String>,
+    suggested_replacement: Option<String>,
+    /// How confident the compiler is that `suggested_replacement` is
+    /// correct. Older compilers do not emit this at all.
+    suggestion_applicability: Option<String>,
     expansion: Option<Box<DiagnosticSpanMacroExpansion>>,
 }
 
+impl DiagnosticSpan {
+    fn col_span(&self) -> ColumnSpan {
+        ColumnSpan {
+            start: self.column_start,
+            end: Some(self.column_end),
+        }
+    }
+}
+
 #[derive(RustcEncodable, RustcDecodable, Clone)]
 struct DiagnosticSpanMacroExpansion {
     /// span where macro was applied to generate this code
@@ -56,22 +69,87 @@ struct DiagnosticCode {
     explanation: Option<String>,
 }
 
+/// A replacement of the source between two (1-based) line/column positions
+/// suggested by the compiler.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Replacement {
+    pub line_start: usize,
+    pub column_start: usize,
+    pub line_end: usize,
+    pub column_end: usize,
+    pub text: String,
+}
+
 pub fn parse_output(file_name: &str, output: &str, proc_res: &ProcRes) -> Vec<Error> {
     output.lines()
         .flat_map(|line| parse_line(file_name, line, output, proc_res))
         .collect()
 }
 
+/// Collects the suggested replacements in `file_name` that can be applied
+/// without a human looking at them first.
+pub fn machine_applicable_replacements(file_name: &str,
+                                       output: &str,
+                                       proc_res: &ProcRes)
+                                       -> Vec<Replacement> {
+    let mut replacements = vec![];
+    for line in output.lines() {
+        if let Some(diagnostic) = decode_line(line, output, proc_res) {
+            push_replacements(&mut replacements, &diagnostic, file_name);
+        }
+    }
+    replacements
+}
+
+fn push_replacements(replacements: &mut Vec<Replacement>,
+                     diagnostic: &Diagnostic,
+                     file_name: &str) {
+    for span in &diagnostic.spans {
+        if Path::new(&span.file_name) != Path::new(&file_name) {
+            continue;
+        }
+        let machine_applicable = match span.suggestion_applicability {
+            Some(ref applicability) => applicability == "MachineApplicable",
+            None => true,
+        };
+        if let Some(ref text) = span.suggested_replacement {
+            if machine_applicable {
+                replacements.push(Replacement {
+                    line_start: span.line_start,
+                    column_start: span.column_start,
+                    line_end: span.line_end,
+                    column_end: span.column_end,
+                    text: text.clone(),
+                });
+            }
+        }
+    }
+    for child in &diagnostic.children {
+        push_replacements(replacements, child, file_name);
+    }
+}
+
 fn parse_line(file_name: &str, line: &str, output: &str, proc_res: &ProcRes) -> Vec<Error> {
+    match decode_line(line, output, proc_res) {
+        Some(mut diagnostic) => {
+            // The rendering of a top-level diagnostic is the complete
+            // human-readable output, only the suggestions of its children
+            // are expected to be matched against.
+            diagnostic.rendered = None;
+            let mut expected_errors = vec![];
+            push_expected_errors(&mut expected_errors, &diagnostic, &[], file_name);
+            expected_errors
+        }
+        None => vec![],
+    }
+}
+
+fn decode_line(line: &str, output: &str, proc_res: &ProcRes) -> Option<Diagnostic> {
     // The compiler sometimes intermingles non-JSON stuff into the
     // output.  This hack just skips over such lines. Yuck.
     if line.chars().next() == Some('{') {
         match json::decode::<Diagnostic>(line) {
-            Ok(diagnostic) => {
-                let mut expected_errors = vec![];
-                push_expected_errors(&mut expected_errors, &diagnostic, &[], file_name);
-                expected_errors
-            }
+            Ok(diagnostic) => Some(diagnostic),
             Err(error) => {
                 proc_res.fatal(Some(&format!("failed to decode compiler output as json: \
                                               `{}`\noutput: {}\nline: {}",
@@ -81,7 +159,7 @@ fn parse_line(file_name: &str, line: &str, output: &str, proc_res: &ProcRes) ->
             }
         }
     } else {
-        vec![]
+        None
     }
 }
 
@@ -148,6 +226,7 @@ fn push_expected_errors(expected_errors: &mut Vec<Error>,
             expected_errors.push(Error {
                 line_num: span.line_start,
                 kind: kind,
+                col_span: Some(span.col_span()),
                 msg: msg,
             });
         }
@@ -157,6 +236,7 @@ fn push_expected_errors(expected_errors: &mut Vec<Error>,
             expected_errors.push(Error {
                 line_num: span.line_start,
                 kind: None,
+                col_span: Some(span.col_span()),
                 msg: with_code(span, next_line),
             });
         }
@@ -170,11 +250,24 @@ fn push_expected_errors(expected_errors: &mut Vec<Error>,
             expected_errors.push(Error {
                 line_num: start_line + index,
                 kind: Some(ErrorKind::Suggestion),
+                col_span: None,
                 msg: line.to_string(),
             });
         }
     }
 
+    // Register the replacement each span of a suggestion asks for.
+    for span in &spans_in_this_file {
+        if let Some(ref replacement) = span.suggested_replacement {
+            expected_errors.push(Error {
+                line_num: span.line_start,
+                kind: Some(ErrorKind::Replacement),
+                col_span: Some(span.col_span()),
+                msg: replacement.clone(),
+            });
+        }
+    }
+
     // Add notes for the backtrace
     for span in primary_spans {
         for frame in &span.expansion {
@@ -188,6 +281,7 @@ fn push_expected_errors(expected_errors: &mut Vec<Error>,
         expected_errors.push(Error {
             line_num: span.line_start,
             kind: Some(ErrorKind::Note),
+            col_span: Some(span.col_span()),
             msg: span.label.clone().unwrap(),
         });
     }
@@ -205,6 +299,7 @@ fn push_backtrace(expected_errors: &mut Vec<Error>,
         expected_errors.push(Error {
             line_num: expansion.span.line_start,
             kind: Some(ErrorKind::Note),
+            col_span: Some(expansion.span.col_span()),
             msg: format!("in this expansion of {}", expansion.macro_decl_name),
         });
     }
@@ -86,8 +86,10 @@ pub fn parse_config(args: Vec<String> ) -> Config {
           reqopt("", "stage-id", "the target-stage identifier", "stageN-TARGET"),
           reqopt("", "mode", "which sort of compile tests to run",
                  "(compile-fail|parse-fail|run-fail|run-pass|\
-                  run-pass-valgrind|pretty|debug-info|incremental|mir-opt)"),
+                  run-pass-valgrind|pretty|debug-info|incremental|mir-opt|\
+                  run-rustfix)"),
           optflag("", "ignored", "run tests marked as ignored"),
+          optflag("", "bless", "overwrite expected output files with the actual output"),
           optopt("", "runtool", "supervisor program to run tests under \
                                  (eg. emulator, valgrind)", "PROGRAM"),
           optopt("", "host-rustcflags", "flags to pass to rustc for host", "FLAGS"),
@@ -162,6 +164,7 @@ pub fn parse_config(args: Vec<String> ) -> Config {
         stage_id: matches.opt_str("stage-id").unwrap(),
         mode: matches.opt_str("mode").unwrap().parse().ok().expect("invalid mode"),
         run_ignored: matches.opt_present("ignored"),
+        bless: matches.opt_present("bless"),
         filter: matches.free.first().cloned(),
         logfile: matches.opt_str("logfile").map(|s| PathBuf::from(&s)),
         runtool: matches.opt_str("runtool"),
@@ -205,6 +208,7 @@ pub fn log_config(config: &Config) {
     logv(c, format!("stage_id: {}", config.stage_id));
     logv(c, format!("mode: {}", config.mode));
     logv(c, format!("run_ignored: {}", config.run_ignored));
+    logv(c, format!("bless: {}", config.bless));
     logv(c, format!("filter: {}",
                     opt_str(&config.filter
                                    .as_ref()
@@ -280,6 +284,10 @@ pub fn run_tests(config: &Config) {
         let _ = fs::remove_dir_all("tmp/partitioning-tests");
     }
 
+    // Aux builds are only cached for the duration of a run: the cache keys
+    // don't cover the compiler's own libraries, which may since have changed.
+    let _ = fs::remove_dir_all(runtest::aux_cache_dir(config));
+
     let opts = test_opts(config);
     let tests = make_tests(config);
     // sadly osx needs some file descriptor limits raised for running tests in
@@ -301,10 +309,16 @@ pub fn run_tests(config: &Config) {
 
 pub fn test_opts(config: &Config) -> test::TestOpts {
     test::TestOpts {
-        filter: config.filter.clone(),
+        filters: config.filter.iter().cloned().collect(),
+        filter_exact: false,
+        skip: vec![],
+        list: false,
+        shard: None,
+        shuffle_seed: None,
         run_ignored: config.run_ignored,
         quiet: config.quiet,
         logfile: config.logfile.clone(),
+        junit: None,
         run_tests: true,
         bench_benchmarks: true,
         nocapture: match env::var("RUST_TEST_NOCAPTURE") {
@@ -312,7 +326,18 @@ pub fn test_opts(config: &Config) -> test::TestOpts {
             Err(_) => false
         },
         color: test::AutoColor,
+        format: test::PrettyFormat,
         test_threads: None,
+        isolate: false,
+        report_time: false,
+        slowest: None,
+        warn_time: None,
+        fail_time: None,
+        bench_warm_up: None,
+        bench_time: None,
+        baseline_dir: None,
+        save_baseline: None,
+        baseline: None,
     }
 }
 
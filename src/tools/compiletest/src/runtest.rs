@@ -11,7 +11,7 @@
 use common::Config;
 use common::{CompileFail, ParseFail, Pretty, RunFail, RunPass, RunPassValgrind};
 use common::{Codegen, DebugInfoLldb, DebugInfoGdb, Rustdoc, CodegenUnits};
-use common::{Incremental, RunMake, Ui, MirOpt};
+use common::{Incremental, RunMake, Ui, MirOpt, RunRustfix};
 use errors::{self, ErrorKind, Error};
 use json;
 use header::TestProps;
@@ -31,6 +31,18 @@ use std::net::TcpStream;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Output, ExitStatus};
 use std::str;
+use std::sync::atomic::{AtomicUsize, ATOMIC_USIZE_INIT, Ordering};
+
+/// Used to give every in-progress aux build its own scratch directory.
+static NEXT_AUX_BUILD: AtomicUsize = ATOMIC_USIZE_INIT;
+
+/// The directory aux crates are built in, shared by all the tests of a run.
+/// Each crate gets a subdirectory named after the hash of everything that
+/// goes into building it, so tests building the same aux crate the same way
+/// reuse it instead of compiling it again.
+pub fn aux_cache_dir(config: &Config) -> PathBuf {
+    config.build_base.join(format!("{}.auxcache", config.mode))
+}
 
 pub fn run(config: Config, testpaths: &TestPaths) {
     match &*config.target {
@@ -118,6 +130,7 @@ impl<'test> TestCx<'test> {
             RunMake => self.run_rmake_test(),
             Ui => self.run_ui_test(),
             MirOpt => self.run_mir_opt_test(),
+            RunRustfix => self.run_rustfix_test(),
         }
     }
 
@@ -263,11 +276,13 @@ impl<'test> TestCx<'test> {
             round += 1;
         }
 
-        let mut expected = match self.props.pp_exact {
-            Some(ref file) => {
-                let filepath = self.testpaths.file.parent().unwrap().join(file);
+        let pp_exact_path = self.props.pp_exact.as_ref().map(|file| {
+            self.testpaths.file.parent().unwrap().join(file)
+        });
+        let mut expected = match pp_exact_path {
+            Some(ref filepath) => {
                 let mut s = String::new();
-                File::open(&filepath).unwrap().read_to_string(&mut s).unwrap();
+                File::open(filepath).unwrap().read_to_string(&mut s).unwrap();
                 s
             }
             None => { srcs[srcs.len() - 2].clone() }
@@ -281,7 +296,12 @@ impl<'test> TestCx<'test> {
             expected = expected.replace(&cr, "").to_owned();
         }
 
-        self.compare_source(&expected, &actual);
+        match pp_exact_path {
+            Some(ref filepath) if self.config.bless && expected != actual => {
+                self.bless_file(filepath, &actual);
+            }
+            _ => self.compare_source(&expected, &actual),
+        }
 
         // If we're only making sure that the output matches then just stop here
         if self.props.pretty_compare_only { return; }
@@ -1012,6 +1032,8 @@ actual:\n\
         // This logic also applies to "note" messages.
         let expect_help = expected_errors.iter().any(|ee| ee.kind == Some(ErrorKind::Help));
         let expect_note = expected_errors.iter().any(|ee| ee.kind == Some(ErrorKind::Note));
+        let expect_replacement =
+            expected_errors.iter().any(|ee| ee.kind == Some(ErrorKind::Replacement));
 
         // Parse the JSON output from the compiler and extract out the messages.
         let actual_errors = json::parse_output(&file_name, &proc_res.stderr, &proc_res);
@@ -1023,11 +1045,7 @@ actual:\n\
                 .iter()
                 .enumerate()
                 .position(|(index, expected_error)| {
-                    !found[index] &&
-                        actual_error.line_num == expected_error.line_num &&
-                        (expected_error.kind.is_none() ||
-                         actual_error.kind == expected_error.kind) &&
-                        actual_error.msg.contains(&expected_error.msg)
+                    !found[index] && expected_error.matches(actual_error)
                 });
 
             match opt_index {
@@ -1038,11 +1056,16 @@ actual:\n\
                 }
 
                 None => {
-                    if self.is_unexpected_compiler_message(actual_error, expect_help, expect_note) {
+                    if self.is_unexpected_compiler_message(actual_error,
+                                                           expect_help,
+                                                           expect_note,
+                                                           expect_replacement) {
                         self.error(
-                            &format!("{}:{}: unexpected {:?}: '{}'",
+                            &format!("{}:{}{}: unexpected {:?}: '{}'",
                                      file_name,
                                      actual_error.line_num,
+                                     actual_error.col_span.map_or(String::new(),
+                                                                  |s| format!(":{}", s)),
                                      actual_error.kind.as_ref()
                                      .map_or(String::from("message"),
                                              |k| k.to_string()),
@@ -1058,9 +1081,11 @@ actual:\n\
         for (index, expected_error) in expected_errors.iter().enumerate() {
             if !found[index] {
                 self.error(
-                    &format!("{}:{}: expected {} not found: {}",
+                    &format!("{}:{}{}: expected {} not found: {}",
                              file_name,
                              expected_error.line_num,
+                             expected_error.col_span.map_or(String::new(),
+                                                            |s| format!(":{}", s)),
                              expected_error.kind.as_ref()
                              .map_or("message".into(),
                                      |k| k.to_string()),
@@ -1088,15 +1113,17 @@ actual:\n\
     /// Returns true if we should report an error about `actual_error`,
     /// which did not match any of the expected error. We always require
     /// errors/warnings to be explicitly listed, but only require
-    /// helps/notes if there are explicit helps/notes given.
+    /// helps/notes/replacements if there are explicit ones given.
     fn is_unexpected_compiler_message(&self,
                                       actual_error: &Error,
                                       expect_help: bool,
-                                      expect_note: bool)
+                                      expect_note: bool,
+                                      expect_replacement: bool)
                                       -> bool {
         match actual_error.kind {
             Some(ErrorKind::Help) => expect_help,
             Some(ErrorKind::Note) => expect_note,
+            Some(ErrorKind::Replacement) => expect_replacement,
             Some(ErrorKind::Error) => true,
             Some(ErrorKind::Warning) => true,
             Some(ErrorKind::Suggestion) => false,
@@ -1201,6 +1228,9 @@ actual:\n\
         let extra_link_args = vec!["-L".to_owned(),
                                    aux_dir.to_str().unwrap().to_owned()];
 
+        // Aux crates may link to the ones built before them, so the cache key
+        // of each one covers all of its predecessors as well.
+        let mut aux_key = FNV_OFFSET_BASIS;
         for rel_ab in &self.props.aux_builds {
             let aux_testpaths = self.compute_aux_test_paths(rel_ab);
             let aux_props = self.props.from_aux_file(&aux_testpaths.file, self.revision);
@@ -1224,29 +1254,38 @@ actual:\n\
                     vec!("--crate-type=dylib".to_owned())
                 }
             };
-            crate_type.extend(extra_link_args.clone());
-            let aux_output = {
-                let f = self.make_lib_name(&self.testpaths.file);
-                let parent = f.parent().unwrap();
-                TargetLocation::ThisDirectory(parent.to_path_buf())
-            };
             let aux_cx = TestCx {
                 config: self.config,
                 props: &aux_props,
                 testpaths: &aux_testpaths,
                 revision: self.revision
             };
-            let aux_args = aux_cx.make_compile_args(crate_type, &aux_testpaths.file, aux_output);
-            let auxres = aux_cx.compose_and_run(aux_args,
-                                                Vec::new(),
-                                                aux_cx.config.compile_lib_path.to_str().unwrap(),
-                                                Some(aux_dir.to_str().unwrap()),
-                                                None);
-            if !auxres.status.success() {
-                self.fatal_proc_rec(
-                    &format!("auxiliary build of {:?} failed to compile: ",
-                             aux_testpaths.file.display()),
-                    &auxres);
+
+            if self.props.incremental_dir.is_some() {
+                // Incremental tests want to see their aux crates rebuilt.
+                crate_type.extend(extra_link_args.clone());
+                self.compile_aux(&aux_cx, crate_type, &aux_dir);
+            } else {
+                aux_key = aux_cx.aux_cache_key(aux_key, &crate_type);
+                let cache_dir = aux_cache_dir(self.config).join(format!("{:016x}", aux_key));
+                if !cache_dir.exists() {
+                    // Build into a scratch directory first, so other tests
+                    // never see a half-built crate in the cache.
+                    let n = NEXT_AUX_BUILD.fetch_add(1, Ordering::SeqCst);
+                    let scratch_dir = cache_dir.with_extension(format!("tmp{}", n));
+                    self.create_dir_racy(&scratch_dir);
+                    crate_type.extend(extra_link_args.clone());
+                    self.compile_aux(&aux_cx, crate_type, &scratch_dir);
+                    // If another test got there first, its build is as good
+                    // as ours.
+                    if fs::rename(&scratch_dir, &cache_dir).is_err() {
+                        let _ = fs::remove_dir_all(&scratch_dir);
+                    }
+                }
+                if let Err(e) = copy_dir_contents(&cache_dir, &aux_dir) {
+                    self.fatal(&format!("failed to copy aux build from `{}`: {}",
+                                        cache_dir.display(), e));
+                }
             }
 
             match &*self.config.target {
@@ -1264,6 +1303,50 @@ actual:\n\
                              input)
     }
 
+    fn compile_aux(&self, aux_cx: &TestCx, extra_args: Vec<String>, out_dir: &Path) {
+        let aux_output = TargetLocation::ThisDirectory(out_dir.to_path_buf());
+        let aux_args = aux_cx.make_compile_args(extra_args,
+                                                &aux_cx.testpaths.file,
+                                                aux_output);
+        let aux_dir = self.aux_output_dir_name();
+        let auxres = aux_cx.compose_and_run(aux_args,
+                                            Vec::new(),
+                                            aux_cx.config.compile_lib_path.to_str().unwrap(),
+                                            Some(aux_dir.to_str().unwrap()),
+                                            None);
+        if !auxres.status.success() {
+            self.fatal_proc_rec(
+                &format!("auxiliary build of {:?} failed to compile: ",
+                         aux_cx.testpaths.file.display()),
+                &auxres);
+        }
+    }
+
+    /// Hashes everything that goes into building this aux crate: its path,
+    /// the compiler and flags it is built with, the target it is built for
+    /// and, through `prev_key`, the aux crates built before it.
+    ///
+    /// The source itself isn't hashed, as the cache only lives for one run
+    /// of compiletest.
+    fn aux_cache_key(&self, prev_key: u64, crate_type: &[String]) -> u64 {
+        let (target, rustcflags) = if self.props.force_host {
+            (&self.config.host, &self.config.host_rustcflags)
+        } else {
+            (&self.config.target, &self.config.target_rustcflags)
+        };
+        let flags = format!("{}\0{}\0{}\0{}\0{:?}\0{:?}\0{:?}\0{:?}\0{}",
+                            self.testpaths.file.display(),
+                            self.config.rustc_path.display(),
+                            self.config.mode,
+                            target,
+                            rustcflags,
+                            self.revision,
+                            self.props.compile_flags,
+                            crate_type,
+                            self.props.no_prefer_dynamic);
+        fnv1a(prev_key, flags.as_bytes())
+    }
+
     // Like std::fs::create_dir_all, except handles concurrent calls among multiple
     // threads or processes.
     fn create_dir_racy(&self, path: &Path) {
@@ -1327,7 +1410,8 @@ actual:\n\
             CompileFail |
             ParseFail |
             RunPass |
-            Incremental => {
+            Incremental |
+            RunRustfix => {
                 // If we are extracting and matching errors in the new
                 // fashion, then you want JSON mode. Old-skool error
                 // patterns still match the raw compiler output.
@@ -2154,7 +2238,8 @@ actual:\n\
         errors += self.compare_output("stderr", &normalized_stderr, &expected_stderr);
 
         if errors > 0 {
-            println!("To update references, run this command from build directory:");
+            println!("To update references, rerun the tests with `--bless` or run this \
+                      command from build directory:");
             let relative_path_to_file =
                 self.testpaths.relative_dir
                               .join(self.testpaths.file.file_name().unwrap());
@@ -2167,6 +2252,57 @@ actual:\n\
         }
     }
 
+    fn run_rustfix_test(&self) {
+        let proc_res = self.compile_test();
+
+        let file_name =
+            format!("{}", self.testpaths.file.display())
+            .replace(r"\", "/"); // on windows, translate all '\' path separators to '/'
+        let replacements = json::machine_applicable_replacements(&file_name,
+                                                                 &proc_res.stderr,
+                                                                 &proc_res);
+        if replacements.is_empty() {
+            self.fatal_proc_rec("no machine-applicable suggestions were emitted", &proc_res);
+        }
+
+        let mut src = String::new();
+        File::open(&self.testpaths.file).unwrap().read_to_string(&mut src).unwrap();
+        let fixed = apply_replacements(&src, &replacements);
+
+        let expected_fixed_path = self.expected_output_path("fixed");
+        let expected_fixed = self.load_expected_output(&expected_fixed_path);
+        if self.compare_output("fixed", &fixed, &expected_fixed) > 0 {
+            self.fatal_proc_rec("the fixed source differed from the expected source",
+                                &proc_res);
+        }
+
+        // Keep the file stem of the test, so the crate name doesn't change.
+        let fixed_path = self.make_out_name("rs");
+        if let Err(e) = File::create(&fixed_path).and_then(|mut f| f.write_all(fixed.as_bytes())) {
+            self.fatal(&format!("failed to write `{}`: {}", fixed_path.display(), e));
+        }
+
+        let aux_dir = self.aux_output_dir_name();
+        let link_args = vec!("-L".to_owned(),
+                             aux_dir.to_str().unwrap().to_owned());
+        let args = self.make_compile_args(link_args,
+                                          &fixed_path,
+                                          TargetLocation::ThisFile(self.make_exe_name()));
+        let proc_res = self.compose_and_run_compiler(args, None);
+        if !proc_res.status.success() {
+            self.fatal_proc_rec("the fixed source failed to compile", &proc_res);
+        }
+
+        let fixed_file_name = format!("{}", fixed_path.display()).replace(r"\", "/");
+        let remaining = json::machine_applicable_replacements(&fixed_file_name,
+                                                              &proc_res.stderr,
+                                                              &proc_res);
+        if !remaining.is_empty() {
+            self.fatal_proc_rec("the fixed source still has machine-applicable suggestions",
+                                &proc_res);
+        }
+    }
+
     fn run_mir_opt_test(&self) {
         let proc_res = self.compile_test();
 
@@ -2187,6 +2323,10 @@ actual:\n\
         fs::File::open(self.testpaths.file.clone()).unwrap()
                                                    .read_to_string(&mut test_file_contents)
                                                    .unwrap();
+        if self.config.bless {
+            self.bless_mir_dump(&test_file_contents);
+            return;
+        }
         if let Some(idx) =  test_file_contents.find("// END RUST SOURCE") {
             let (_, tests_text) = test_file_contents.split_at(idx + "// END_RUST SOURCE".len());
             let tests_text_str = String::from(tests_text);
@@ -2215,16 +2355,86 @@ actual:\n\
         }
     }
 
+    /// Rewrites the lines of the `// START`/`// END` blocks of the test file
+    /// which no longer match the MIR dump. The expectations are usually only
+    /// part of the dump, so matching lines are kept and every mismatching
+    /// line is replaced with the next line of the dump which isn't matched
+    /// yet, rather than pasting the whole dump.
+    fn bless_mir_dump(&self, test_file_contents: &str) {
+        let mut blessed = String::new();
+        let mut after_source = false;
+        // The non-empty lines of the dump of the current block, and how many
+        // of them the expectations have gone past.
+        let mut dump: Option<(Vec<String>, usize)> = None;
+        for line in test_file_contents.lines() {
+            if !after_source {
+                after_source = line.starts_with("// END RUST SOURCE");
+            } else if line.starts_with("// START ") {
+                let dumped_lines = self.read_mir_dump(&line["// START ".len()..])
+                                       .lines()
+                                       .filter(|l| !l.is_empty())
+                                       .map(|l| l.to_string())
+                                       .collect();
+                dump = Some((dumped_lines, 0));
+            } else if line.starts_with("// END") {
+                dump = None;
+            } else if let Some((ref dumped_lines, ref mut next)) = dump {
+                let expected = if line.starts_with("// ") {
+                    normalize_mir_line(&line["// ".len()..])
+                } else {
+                    String::new()
+                };
+                if !expected.is_empty() {
+                    let found = dumped_lines[*next..].iter().position(|l| {
+                        normalize_mir_line(l) == expected
+                    });
+                    match found {
+                        Some(i) => *next += i + 1,
+                        None if *next < dumped_lines.len() => {
+                            println!("blessing `{}` as `// {}`", line, dumped_lines[*next]);
+                            blessed.push_str("// ");
+                            blessed.push_str(&dumped_lines[*next]);
+                            blessed.push('\n');
+                            *next += 1;
+                            continue;
+                        }
+                        None => {
+                            println!("removing `{}`, which is past the end of the dump", line);
+                            continue;
+                        }
+                    }
+                }
+            }
+            blessed.push_str(line);
+            blessed.push('\n');
+        }
+        if blessed != test_file_contents {
+            self.bless_file(&self.testpaths.file, &blessed);
+        }
+    }
+
     fn compare_mir_test_output(&self, test_name: &str, expected_content: &Vec<&str>) {
+        if !self.mir_dump_matches(test_name, expected_content) {
+            panic!("ran out of mir dump output to match against");
+        }
+    }
+
+    fn read_mir_dump(&self, test_name: &str) -> String {
         let mut output_file = PathBuf::new();
         output_file.push(self.get_mir_dump_dir());
         output_file.push(test_name);
-        debug!("comparing the contests of: {:?}", output_file);
-        debug!("with: {:?}", expected_content);
+        debug!("reading the contents of: {:?}", output_file);
 
-        let mut dumped_file = fs::File::open(output_file.clone()).unwrap();
+        let mut dumped_file = fs::File::open(output_file).unwrap();
         let mut dumped_string = String::new();
         dumped_file.read_to_string(&mut dumped_string).unwrap();
+        dumped_string
+    }
+
+    fn mir_dump_matches(&self, test_name: &str, expected_content: &[&str]) -> bool {
+        debug!("comparing {} with: {:?}", test_name, expected_content);
+
+        let dumped_string = self.read_mir_dump(test_name);
         let mut dumped_lines = dumped_string.lines().filter(|l| !l.is_empty());
         let mut expected_lines = expected_content.iter().filter(|l| !l.is_empty());
 
@@ -2246,9 +2456,10 @@ actual:\n\
                 };
             }
             if !found {
-                panic!("ran out of mir dump output to match against");
+                return false;
             }
         }
+        true
     }
 
     fn get_mir_dump_dir(&self) -> PathBuf {
@@ -2294,6 +2505,16 @@ actual:\n\
     }
 
     fn compare_output(&self, kind: &str, actual: &str, expected: &str) -> usize {
+        if self.config.bless {
+            // An empty expected file is equivalent to a missing one, so make
+            // sure stale ones get removed even when the output "matches".
+            let expected_path = self.expected_output_path(kind);
+            if actual != expected || (actual.is_empty() && expected_path.exists()) {
+                self.bless_file(&expected_path, actual);
+            }
+            return 0;
+        }
+
         if actual == expected {
             return 0;
         }
@@ -2318,6 +2539,21 @@ actual:\n\
         println!("Actual {} saved to {}", kind, output_file.display());
         1
     }
+
+    /// Overwrites the expected output at `path` with `contents`, removing the
+    /// file instead if there is no output to expect any more.
+    fn bless_file(&self, path: &Path, contents: &str) {
+        let res = if contents.is_empty() {
+            println!("removing stale {}", path.display());
+            fs::remove_file(path)
+        } else {
+            println!("blessing {}", path.display());
+            File::create(path).and_then(|mut f| f.write_all(contents.as_bytes()))
+        };
+        if let Err(e) = res {
+            self.fatal(&format!("failed to bless `{}`: {}", path.display(), e));
+        }
+    }
 }
 
 struct ProcArgs {
@@ -2390,6 +2626,57 @@ enum TargetLocation {
     ThisDirectory(PathBuf),
 }
 
+/// Splices the suggested replacements into `src`. Identical suggestions are
+/// applied once; a suggestion overlapping an earlier one is dropped.
+fn apply_replacements(src: &str, replacements: &[json::Replacement]) -> String {
+    let mut line_starts = vec![0];
+    line_starts.extend(src.char_indices().filter(|&(_, c)| c == '\n').map(|(i, _)| i + 1));
+    // Lines and columns are 1-based, and columns count characters, not bytes.
+    let offset = |line: usize, column: usize| {
+        let line_start = line_starts[line - 1];
+        src[line_start..].char_indices()
+                         .nth(column - 1)
+                         .map_or(src.len(), |(i, _)| line_start + i)
+    };
+
+    let mut ranges = replacements.iter().map(|r| {
+        (offset(r.line_start, r.column_start), offset(r.line_end, r.column_end), &r.text[..])
+    }).collect::<Vec<_>>();
+    ranges.sort();
+    ranges.dedup();
+
+    let mut fixed = String::new();
+    let mut pos = 0;
+    for (lo, hi, text) in ranges {
+        if lo < pos {
+            continue;
+        }
+        fixed.push_str(&src[pos..lo]);
+        fixed.push_str(text);
+        pos = hi;
+    }
+    fixed.push_str(&src[pos..]);
+    fixed
+}
+
+fn copy_dir_contents(from: &Path, to: &Path) -> io::Result<()> {
+    for entry in fs::read_dir(from)? {
+        let path = entry?.path();
+        fs::copy(&path, to.join(path.file_name().unwrap()))?;
+    }
+    Ok(())
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for &byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
 fn normalize_mir_line(line: &str) -> String {
     let no_comments = if let Some(idx) = line.find("//") {
         let (l, _) = line.split_at(idx);
@@ -0,0 +1,183 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Configuration of the style checks, read from `src/tools/tidy/style.conf`.
+//!
+//! The configuration is made of sections, each starting with a pattern in
+//! square brackets and followed by `name = value` settings:
+//!
+//! ```text
+//! # Comments start with a hash
+//! [*]
+//! linelength = 100
+//! todo = on
+//!
+//! [libstd/sync/mpsc/*_queue.rs]
+//! license = off
+//! ```
+//!
+//! Patterns are relative to the `src` directory. A section applies to a file
+//! if its pattern matches the path of the file or of any directory containing
+//! it, so `[librustc*]` covers all files of the compiler crates. Within a path
+//! component `*` matches any sequence of characters and `?` any single
+//! character, while a `**` component matches any number of directories. When
+//! several sections set the same name the last one wins.
+//!
+//! Values are either `on`, `off` or a number, the latter being a limit for
+//! checks taking one. Besides the names of the checks the `skip` setting
+//! excludes files and directories from all style checks.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::{Component, Path};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Setting {
+    On,
+    Off,
+    Limit(usize),
+}
+
+struct Section {
+    pattern: Vec<String>,
+    settings: Vec<(String, Setting)>,
+}
+
+pub struct Config {
+    sections: Vec<Section>,
+}
+
+/// The settings applying to a particular file or directory.
+pub struct Rules<'a> {
+    settings: HashMap<&'a str, Setting>,
+}
+
+impl Config {
+    pub fn load(file: &Path) -> Config {
+        let mut contents = String::new();
+        t!(t!(File::open(file), file).read_to_string(&mut contents));
+
+        let mut sections = Vec::new();
+        for (i, line) in contents.lines().enumerate() {
+            let line = match line.find('#') {
+                Some(pos) => &line[..pos],
+                None => line,
+            }.trim();
+            if line.is_empty() {
+                continue
+            }
+            let error = |msg: &str| -> ! {
+                panic!("{}:{}: {}", file.display(), i + 1, msg)
+            };
+
+            if line.starts_with('[') && line.ends_with(']') {
+                let pattern = line[1..line.len() - 1].trim();
+                sections.push(Section {
+                    pattern: pattern.split('/').map(|s| s.to_string()).collect(),
+                    settings: Vec::new(),
+                });
+                continue
+            }
+
+            let mut parts = line.splitn(2, '=').map(|s| s.trim());
+            let name = parts.next().unwrap();
+            let value = match parts.next() {
+                Some(value) => value,
+                None => error("expected a section or a `name = value` setting"),
+            };
+            let setting = match value {
+                "on" => Setting::On,
+                "off" => Setting::Off,
+                n => match n.parse() {
+                    Ok(n) => Setting::Limit(n),
+                    Err(..) => error(&format!("invalid value `{}`", value)),
+                },
+            };
+            match sections.last_mut() {
+                Some(section) => section.settings.push((name.to_string(), setting)),
+                None => error("setting outside of any section"),
+            }
+        }
+        Config { sections: sections }
+    }
+
+    /// Returns all names set anywhere in the configuration.
+    pub fn names(&self) -> Vec<&str> {
+        self.sections.iter()
+            .flat_map(|s| s.settings.iter().map(|&(ref name, _)| &name[..]))
+            .collect()
+    }
+
+    /// Returns the rules applying to `path`, which is located in `root`.
+    pub fn rules<'a>(&'a self, root: &Path, path: &Path) -> Rules<'a> {
+        let path = t!(path.strip_prefix(root)).components().filter_map(|c| {
+            match c {
+                Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+                _ => None,
+            }
+        }).collect::<Vec<_>>();
+
+        let mut settings = HashMap::new();
+        for section in self.sections.iter() {
+            let applies = (1..path.len() + 1).any(|len| {
+                matches(&section.pattern, &path[..len])
+            });
+            if applies {
+                for &(ref name, setting) in section.settings.iter() {
+                    settings.insert(&name[..], setting);
+                }
+            }
+        }
+        Rules { settings: settings }
+    }
+}
+
+impl<'a> Rules<'a> {
+    /// Returns the setting of `name`, checks which aren't configured are off.
+    pub fn get(&self, name: &str) -> Setting {
+        self.settings.get(name).cloned().unwrap_or(Setting::Off)
+    }
+
+    pub fn skip(&self) -> bool {
+        self.get("skip") != Setting::Off
+    }
+}
+
+fn matches(pattern: &[String], path: &[String]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((first, rest)) if first == "**" => {
+            (0..path.len() + 1).any(|i| matches(rest, &path[i..]))
+        }
+        Some((first, rest)) => match path.split_first() {
+            Some((name, path)) => {
+                component_matches(first.as_bytes(), name.as_bytes()) &&
+                    matches(rest, path)
+            }
+            None => false,
+        },
+    }
+}
+
+fn component_matches(pattern: &[u8], name: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((&b'*', rest)) => {
+            (0..name.len() + 1).any(|i| component_matches(rest, &name[i..]))
+        }
+        Some((&b'?', rest)) => {
+            !name.is_empty() && component_matches(rest, &name[1..])
+        }
+        Some((c, rest)) => {
+            name.first() == Some(c) && component_matches(rest, &name[1..])
+        }
+    }
+}
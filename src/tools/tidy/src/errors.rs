@@ -11,27 +11,100 @@
 //! Tidy check to verify the validity of long error diagnostic codes.
 //!
 //! This ensures that error codes are used at most once and also prints out some
-//! statistics about the error codes.
+//! statistics about the error codes. Additionally every error code must have a
+//! long-form explanation, be exercised by a compile-fail or ui test, and be
+//! emitted somewhere in the compiler if it's explained. The codes which don't
+//! meet these requirements yet are listed below.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::prelude::*;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+/// Error codes registered without a long-form explanation.
+const UNEXPLAINED: &'static [&'static str] = &[
+    "E0090", "E0103", "E0104", "E0182", "E0183", "E0196", "E0203", "E0208",
+    "E0212", "E0224", "E0226", "E0227", "E0228", "E0230", "E0231", "E0245",
+    "E0278", "E0279", "E0280", "E0284", "E0298", "E0311", "E0313", "E0314",
+    "E0315", "E0316", "E0320", "E0328", "E0377", "E0385", "E0399", "E0402",
+    "E0436", "E0456", "E0457", "E0460", "E0461", "E0462", "E0464", "E0465",
+    "E0466", "E0467", "E0468", "E0469", "E0470", "E0472", "E0473", "E0474",
+    "E0475", "E0476", "E0477", "E0479", "E0480", "E0481", "E0482", "E0483",
+    "E0484", "E0485", "E0486", "E0487", "E0488", "E0489", "E0490", "E0491",
+    "E0495", "E0498", "E0513", "E0514", "E0519", "E0521", "E0523", "E0524",
+    "E0526", "E0531", "E0532", "E0533", "E0538", "E0539", "E0540", "E0541",
+    "E0542", "E0543", "E0544", "E0545", "E0546", "E0547", "E0548", "E0549",
+    "E0550", "E0551", "E0552", "E0553", "E0554", "E0555", "E0556", "E0557",
+    "E0561", "E0562", "E0563", "E0564", "E0566",
+];
+
+/// Error codes not appearing in any compile-fail or ui test.
+const UNTESTED: &'static [&'static str] = &[
+    "E0003", "E0014", "E0019", "E0020", "E0027", "E0029", "E0030", "E0066",
+    "E0069", "E0073", "E0074", "E0081", "E0082", "E0090", "E0103", "E0104",
+    "E0122", "E0136", "E0139", "E0154", "E0170", "E0182", "E0183", "E0193",
+    "E0196", "E0198", "E0202", "E0203", "E0205", "E0206", "E0208", "E0211",
+    "E0212", "E0224", "E0226", "E0227", "E0228", "E0230", "E0231", "E0245",
+    "E0251", "E0256", "E0270", "E0272", "E0273", "E0274", "E0278", "E0279",
+    "E0280", "E0284", "E0298", "E0311", "E0313", "E0314", "E0315", "E0316",
+    "E0320", "E0328", "E0329", "E0364", "E0366", "E0369", "E0377", "E0380",
+    "E0381", "E0382", "E0383", "E0384", "E0385", "E0386", "E0387", "E0398",
+    "E0399", "E0402", "E0436", "E0447", "E0448", "E0453", "E0455", "E0456",
+    "E0457", "E0460", "E0461", "E0462", "E0464", "E0465", "E0466", "E0467",
+    "E0468", "E0469", "E0470", "E0472", "E0473", "E0474", "E0475", "E0476",
+    "E0477", "E0479", "E0480", "E0481", "E0482", "E0483", "E0484", "E0485",
+    "E0486", "E0487", "E0488", "E0489", "E0490", "E0491", "E0497", "E0498",
+    "E0500", "E0513", "E0514", "E0519", "E0521", "E0523", "E0524", "E0526",
+    "E0531", "E0532", "E0533", "E0538", "E0543", "E0545", "E0549", "E0550",
+    "E0551", "E0552", "E0553", "E0554", "E0555", "E0556", "E0557", "E0561",
+    "E0562", "E0563", "E0564", "E0566",
+];
+
+/// Error codes with a long-form explanation which the compiler doesn't emit
+/// anymore.
+const UNEMITTED: &'static [&'static str] = &[
+    "E0014", "E0020", "E0066", "E0073", "E0074", "E0082", "E0139", "E0154",
+    "E0193", "E0211", "E0251", "E0256", "E0329", "E0398", "E0447", "E0448",
+    "E0497",
+];
+
+/// Test suites which are expected to exercise every error code.
+const TEST_SUITES: &'static [&'static str] = &[
+    "test/compile-fail",
+    "test/compile-fail-fulldeps",
+    "test/ui",
+];
 
 pub fn check(path: &Path, bad: &mut bool) {
     let mut contents = String::new();
     let mut map = HashMap::new();
+    let mut explained = HashMap::new();
+    let mut unexplained = HashMap::new();
+    let mut emitted = HashSet::new();
     super::walk(path,
-                &mut |path| super::filter_dirs(path) || path.ends_with("src/test"),
+                &mut |path| {
+                    super::filter_dirs(path) ||
+                        path.ends_with("src/test") ||
+                        path.ends_with("src/tools")
+                },
                 &mut |file| {
         let filename = file.file_name().unwrap().to_string_lossy();
-        if filename != "diagnostics.rs" && filename != "diagnostic_list.rs" {
+        if !filename.ends_with(".rs") {
             return
         }
 
         contents.truncate(0);
         t!(t!(File::open(file)).read_to_string(&mut contents));
 
+        if filename != "diagnostics.rs" && filename != "diagnostic_list.rs" {
+            for line in contents.lines() {
+                if !line.trim().starts_with("//") {
+                    emitted.extend(error_codes(line));
+                }
+            }
+            return
+        }
+
         // In the register_long_diagnostics! macro, entries look like this:
         //
         // EXXXX: r##"
@@ -43,11 +116,28 @@ pub fn check(path: &Path, bad: &mut bool) {
         // variable keeps track of whether we're currently inside one of these
         // long diagnostic messages.
         let mut inside_long_diag = false;
+        // Whether we're inside the `register_diagnostics!` macro, listing the
+        // error codes without a long diagnostic message.
+        let mut inside_short_diags = false;
         for (num, line) in contents.lines().enumerate() {
             if inside_long_diag {
                 inside_long_diag = !line.contains("\"##");
                 continue
             }
+            if line.contains("register_diagnostics!") {
+                inside_short_diags = true;
+            } else if line.starts_with("}") {
+                inside_short_diags = false;
+            }
+
+            let location = (file.to_owned(), num + 1);
+            if let Some(&code) = error_codes(line).first() {
+                if line.contains("r##\"") {
+                    explained.insert(code, location);
+                } else if inside_short_diags && !line.trim().starts_with("//") {
+                    unexplained.insert(code, location);
+                }
+            }
 
             let mut search = line;
             while let Some(i) = search.find("E") {
@@ -86,8 +176,83 @@ pub fn check(path: &Path, bad: &mut bool) {
         *bad = true;
     }
 
+    let mut tested = HashSet::new();
+    for suite in TEST_SUITES {
+        super::walk(&path.join(suite), &mut |_| false, &mut |file| {
+            let filename = file.file_name().unwrap().to_string_lossy();
+            if !filename.ends_with(".rs") && !filename.ends_with(".stderr") {
+                return
+            }
+
+            contents.truncate(0);
+            t!(t!(File::open(file)).read_to_string(&mut contents));
+            for line in contents.lines() {
+                tested.extend(error_codes(line));
+            }
+        });
+    }
+
+    for (&code, location) in explained.iter().chain(unexplained.iter()) {
+        requirement(UNEXPLAINED, code, location, !unexplained.contains_key(&code),
+                    "has no long-form explanation", bad);
+        requirement(UNTESTED, code, location, tested.contains(&code),
+                    "doesn't appear in any compile-fail or ui test", bad);
+    }
+    for (&code, location) in explained.iter() {
+        requirement(UNEMITTED, code, location, emitted.contains(&code),
+                    "is explained but never emitted", bad);
+    }
+    let registered = |name: &&str| {
+        let code = name[1..].parse().unwrap();
+        explained.contains_key(&code) || unexplained.contains_key(&code)
+    };
+    for name in UNEXPLAINED.iter().chain(UNTESTED).chain(UNEMITTED) {
+        if !registered(name) {
+            println!("{} is listed as an exception in errors.rs but isn't registered", name);
+            *bad = true;
+        }
+    }
+
     if !*bad {
         println!("* {} error codes", map.len());
         println!("* highest error code: E{:04}", max);
     }
 }
+
+/// Reports `code` if it doesn't satisfy a requirement (`ok` is false) and it
+/// isn't listed in the `exceptions`, or if it does but is still listed there.
+fn requirement(exceptions: &[&str],
+               code: u32,
+               &(ref file, line): &(PathBuf, usize),
+               ok: bool,
+               msg: &str,
+               bad: &mut bool) {
+    let name = format!("E{:04}", code);
+    let excepted = exceptions.contains(&&name[..]);
+    if !ok && !excepted {
+        println!("{}:{}: error code {} {}", file.display(), line, name, msg);
+        *bad = true;
+    } else if ok && excepted {
+        println!("{}:{}: error code {} is listed as an exception in errors.rs but \
+                  doesn't need to be anymore", file.display(), line, name);
+        *bad = true;
+    }
+}
+
+/// Returns the error codes mentioned in `line`, in order.
+fn error_codes(line: &str) -> Vec<u32> {
+    let bytes = line.as_bytes();
+    let mut codes = Vec::new();
+    let boundary = |b: Option<&u8>| b.map_or(true, |&b| !(b as char).is_alphanumeric());
+    for (i, _) in line.match_indices("E") {
+        let digits = &bytes[i + 1..];
+        if digits.len() < 4 || !digits[..4].iter().all(|&b| (b as char).is_digit(10)) {
+            continue
+        }
+        let before = if i == 0 { None } else { bytes.get(i - 1) };
+        if boundary(before) && boundary(digits.get(4)) {
+            codes.push(line[i + 1..i + 5].parse().unwrap());
+        }
+    }
+    codes
+}
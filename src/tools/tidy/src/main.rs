@@ -31,6 +31,7 @@ macro_rules! t {
 }
 
 mod bins;
+mod config;
 mod style;
 mod errors;
 mod features;
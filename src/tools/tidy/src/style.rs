@@ -18,14 +18,23 @@
 //! * No CR characters
 //! * No `TODO` or `XXX` directives
 //! * A valid license header is at the top
+//! * No `println!` in the compiler
 //!
-//! A number of these checks can be opted-out of with various directives like
+//! Which checks apply where, and with which limits, is configured in
+//! `src/tools/tidy/style.conf`, see the `config` module for its format. On top
+//! of that a single file can opt out of a check with a directive like
 //! `// ignore-tidy-linelength`.
+//!
+//! New checks are added to `LINE_CHECKS` or `FILE_CHECKS` below and then
+//! enabled in the configuration.
 
 use std::fs::File;
 use std::io::prelude::*;
 use std::path::Path;
 
+use config::{Config, Setting};
+
+/// Line length used when `linelength` is turned on without a limit.
 const COLS: usize = 100;
 const LICENSE: &'static str = "\
 Copyright <year> The Rust Project Developers. See the COPYRIGHT
@@ -38,69 +47,167 @@ http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
 option. This file may not be copied, modified, or distributed
 except according to those terms.";
 
+/// A check run over each line of a file, returning an error message if the
+/// line is bad.
+struct LineCheck {
+    /// Name of the check in the configuration.
+    name: &'static str,
+    /// Directive opting a file out of the check.
+    directive: &'static str,
+    check: fn(line: &str, setting: Setting) -> Option<String>,
+}
+
+/// A check run over the whole contents of a file, returning an error message
+/// if the file is bad.
+struct FileCheck {
+    name: &'static str,
+    directive: &'static str,
+    check: fn(file: &Path, contents: &str, setting: Setting) -> Option<String>,
+}
+
+const LINE_CHECKS: &'static [LineCheck] = &[
+    LineCheck { name: "linelength", directive: "ignore-tidy-linelength", check: linelength },
+    LineCheck { name: "tab", directive: "ignore-tidy-tab", check: tab },
+    LineCheck {
+        name: "trailing-whitespace",
+        directive: "ignore-tidy-trailing-whitespace",
+        check: trailing_whitespace,
+    },
+    LineCheck { name: "cr", directive: "ignore-tidy-cr", check: cr },
+    LineCheck { name: "todo", directive: "ignore-tidy-todo", check: todo },
+    LineCheck { name: "xxx", directive: "ignore-tidy-xxx", check: xxx },
+    LineCheck { name: "println", directive: "ignore-tidy-println", check: println },
+];
+
+const FILE_CHECKS: &'static [FileCheck] = &[
+    FileCheck { name: "license", directive: "ignore-license", check: license },
+];
+
 pub fn check(path: &Path, bad: &mut bool) {
+    let config = Config::load(&path.join("tools/tidy/style.conf"));
+    for name in config.names() {
+        let known = name == "skip" ||
+                    LINE_CHECKS.iter().any(|c| c.name == name) ||
+                    FILE_CHECKS.iter().any(|c| c.name == name);
+        if !known {
+            panic!("unknown style check `{}` in style.conf", name);
+        }
+    }
+
     let mut contents = String::new();
-    super::walk(path, &mut super::filter_dirs, &mut |file| {
+    let mut skip = |dir: &Path| {
+        super::filter_dirs(dir) || config.rules(path, dir).skip()
+    };
+    super::walk(path, &mut skip, &mut |file| {
         let filename = file.file_name().unwrap().to_string_lossy();
         let extensions = [".rs", ".py", ".js", ".sh", ".c", ".h"];
         if extensions.iter().all(|e| !filename.ends_with(e)) ||
            filename.starts_with(".#") {
             return
         }
-        if filename == "miniz.c" || filename.contains("jquery") {
+        let rules = config.rules(path, file);
+        if rules.skip() {
             return
         }
 
         contents.truncate(0);
         t!(t!(File::open(file), file).read_to_string(&mut contents));
-        let skip_cr = contents.contains("ignore-tidy-cr");
-        let skip_tab = contents.contains("ignore-tidy-tab");
-        let skip_length = contents.contains("ignore-tidy-linelength");
+        let line_checks = LINE_CHECKS.iter().map(|c| (c, rules.get(c.name))).filter(|&(c, s)| {
+            s != Setting::Off && !contents.contains(c.directive)
+        }).collect::<Vec<_>>();
         for (i, line) in contents.split("\n").enumerate() {
-            let mut err = |msg: &str| {
-                println!("{}:{}: {}", file.display(), i + 1, msg);
-                *bad = true;
-            };
-            if line.chars().count() > COLS && !skip_length {
-                err(&format!("line longer than {} chars", COLS));
-            }
-            if line.contains("\t") && !skip_tab {
-                err("tab character");
-            }
-            if line.ends_with(" ") || line.ends_with("\t") {
-                err("trailing whitespace");
-            }
-            if line.contains("\r") && !skip_cr {
-                err("CR character");
-            }
-            if filename != "style.rs" {
-                if line.contains("TODO") {
-                    err("TODO is deprecated; use FIXME")
-                }
-                if line.contains("//") && line.contains(" XXX") {
-                    err("XXX is deprecated; use FIXME")
+            for &(check, setting) in line_checks.iter() {
+                if let Some(msg) = (check.check)(line, setting) {
+                    println!("{}:{}: {}", file.display(), i + 1, msg);
+                    *bad = true;
                 }
             }
         }
-        if !licenseck(file, &contents) {
-            println!("{}: incorrect license", file.display());
-            *bad = true;
+        for check in FILE_CHECKS.iter() {
+            let setting = rules.get(check.name);
+            if setting == Setting::Off || contents.contains(check.directive) {
+                continue
+            }
+            if let Some(msg) = (check.check)(file, &contents, setting) {
+                println!("{}: {}", file.display(), msg);
+                *bad = true;
+            }
         }
     })
 }
 
-fn licenseck(file: &Path, contents: &str) -> bool {
-    if contents.contains("ignore-license") {
-        return true
+fn linelength(line: &str, setting: Setting) -> Option<String> {
+    let cols = match setting {
+        Setting::Limit(cols) => cols,
+        _ => COLS,
+    };
+    if line.chars().count() > cols {
+        Some(format!("line longer than {} chars", cols))
+    } else {
+        None
     }
-    let exceptions = [
-        "libstd/sync/mpsc/mpsc_queue.rs",
-        "libstd/sync/mpsc/spsc_queue.rs",
-    ];
-    if exceptions.iter().any(|f| file.ends_with(f)) {
-        return true
+}
+
+fn tab(line: &str, _: Setting) -> Option<String> {
+    if line.contains("\t") {
+        Some("tab character".to_string())
+    } else {
+        None
+    }
+}
+
+fn trailing_whitespace(line: &str, _: Setting) -> Option<String> {
+    if line.ends_with(" ") || line.ends_with("\t") {
+        Some("trailing whitespace".to_string())
+    } else {
+        None
+    }
+}
+
+fn cr(line: &str, _: Setting) -> Option<String> {
+    if line.contains("\r") {
+        Some("CR character".to_string())
+    } else {
+        None
+    }
+}
+
+fn todo(line: &str, _: Setting) -> Option<String> {
+    if line.contains("TODO") {
+        Some("TODO is deprecated; use FIXME".to_string())
+    } else {
+        None
     }
+}
+
+fn xxx(line: &str, _: Setting) -> Option<String> {
+    if line.contains("//") && line.contains(" XXX") {
+        Some("XXX is deprecated; use FIXME".to_string())
+    } else {
+        None
+    }
+}
+
+/// The compiler talks to its users through diagnostics, and debugging output
+/// goes through `debug!` and friends, so `println!` in the compiler is usually
+/// a leftover.
+fn println(line: &str, _: Setting) -> Option<String> {
+    if !line.trim().starts_with("//") && line.contains("println!(") {
+        Some("println! in compiler code; use a diagnostic or debug!".to_string())
+    } else {
+        None
+    }
+}
+
+fn license(_: &Path, contents: &str, _: Setting) -> Option<String> {
+    if licenseck(contents) {
+        None
+    } else {
+        Some("incorrect license".to_string())
+    }
+}
 
+fn licenseck(contents: &str) -> bool {
     // Skip the BOM if it's there
     let bom = "\u{feff}";
     let contents = if contents.starts_with(bom) {&contents[3..]} else {contents};